@@ -0,0 +1,166 @@
+use cosmwasm_std::{Addr, CosmosMsg, Deps, DepsMut, MessageInfo, Order, Response, StdResult, Storage, Uint128};
+
+use crate::contract::execute::{route_fee, transfer};
+use crate::msg::{BetResponse, BetTotal, BetsResponse};
+use crate::state::{Bet, Game, StakeAsset, BETS, BET_TOTALS, GAMES, PLAYERS};
+use crate::ContractError;
+
+/// Places a spectator bet sent through the token the game is staked in. Bets
+/// are taken until the configured number of shots has been resolved.
+pub fn place_bet(
+    deps: DepsMut,
+    asset: &StakeAsset,
+    bettor: Addr,
+    game_id: u64,
+    player: String,
+    amount: Uint128
+) -> Result<Response, ContractError> {
+    let game = GAMES.load(deps.storage, game_id)?;
+
+    if *asset != game.config.asset {
+        return Err(ContractError::InvalidToken {});
+    }
+
+    if !betting_open(&game) {
+        return Err(ContractError::BettingClosed {});
+    }
+
+    let player = deps.api.addr_validate(&player)?;
+    if !PLAYERS.has(deps.storage, (game_id, &player)) {
+        return Err(ContractError::PlayerNotFound {});
+    }
+
+    if bettor != player && PLAYERS.has(deps.storage, (game_id, &bettor)) {
+        return Err(ContractError::BetAgainstSelf {});
+    }
+
+    let mut bet = BETS
+        .may_load(deps.storage, (game_id, &bettor))?
+        .unwrap_or(Bet { player: player.clone(), amount: Uint128::zero() });
+    if bet.player != player {
+        return Err(ContractError::BetOnOpponent {});
+    }
+
+    bet.amount += amount;
+    BETS.save(deps.storage, (game_id, &bettor), &bet)?;
+    BET_TOTALS.update::<_, ContractError>(deps.storage, (game_id, &player), |total| {
+        Ok(total.unwrap_or_default() + amount)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "place_bet")
+        .add_attribute("game_id", game_id.to_string())
+        .add_attribute("bettor", bettor.to_string())
+        .add_attribute("player", player.to_string())
+        .add_attribute("amount", amount.to_string())
+    )
+}
+
+/// Pays out a bet on a settled game. Bets on the player who got the pot
+/// share the bets on the other player pro rata, minus the house fee. All
+/// bets are refunded when the game ended without anybody getting the pot or
+/// nobody backed the player who did.
+pub fn claim_bet(
+    deps: DepsMut,
+    info: MessageInfo,
+    game_id: u64
+) -> Result<Response, ContractError> {
+    let game = GAMES.load(deps.storage, game_id)?;
+
+    if !game.state.settled {
+        return Err(ContractError::GameNotFinished {});
+    }
+
+    let bet = BETS
+        .may_load(deps.storage, (game_id, &info.sender))?
+        .ok_or(ContractError::NoBet {})?;
+    BETS.remove(deps.storage, (game_id, &info.sender));
+
+    let payout = match &game.state.paid_to {
+        Some(paid_to) => {
+            let (won, lost) = pools(deps.storage, game_id, paid_to)?;
+            if won.is_zero() {
+                bet.amount
+            } else if bet.player == *paid_to {
+                let fee = lost.multiply_ratio(game.config.fee_percentage, 100u128);
+                bet.amount + (lost - fee).multiply_ratio(bet.amount, won)
+            } else {
+                Uint128::zero()
+            }
+        }
+        None => bet.amount,
+    };
+
+    let mut response = Response::new()
+        .add_attribute("action", "claim_bet")
+        .add_attribute("game_id", game_id.to_string())
+        .add_attribute("bettor", info.sender.to_string())
+        .add_attribute("payout", payout.to_string());
+    if !payout.is_zero() {
+        response = response.add_message(transfer(info.sender, payout, &game.config.asset)?);
+    }
+
+    Ok(response)
+}
+
+/// Routes the house fee taken from the bets lost on a game being settled.
+/// Nothing is taken when the bets are refunded.
+pub fn take_house_fee(
+    storage: &mut dyn Storage,
+    game_id: u64,
+    game: &Game
+) -> Result<Option<CosmosMsg>, ContractError> {
+    let Some(paid_to) = &game.state.paid_to else {
+        return Ok(None);
+    };
+
+    let (won, lost) = pools(storage, game_id, paid_to)?;
+    if won.is_zero() {
+        return Ok(None);
+    }
+
+    let fee = lost.multiply_ratio(game.config.fee_percentage, 100u128);
+    route_fee(storage, &game.config.asset, fee)
+}
+
+fn betting_open(game: &Game) -> bool {
+    !game.state.finished && game.state.moves < game.config.betting_moves
+}
+
+/// Totals bet on the player who got the pot and on the other player.
+fn pools(storage: &dyn Storage, game_id: u64, paid_to: &Addr) -> StdResult<(Uint128, Uint128)> {
+    let mut won = Uint128::zero();
+    let mut lost = Uint128::zero();
+    for item in BET_TOTALS.prefix(game_id).range(storage, None, None, Order::Ascending) {
+        let (player, amount) = item?;
+        if player == *paid_to {
+            won += amount;
+        } else {
+            lost += amount;
+        }
+    }
+    Ok((won, lost))
+}
+
+pub fn get_bets(deps: Deps, game_id: u64) -> StdResult<BetsResponse> {
+    let game = GAMES.load(deps.storage, game_id)?;
+    let totals = BET_TOTALS
+        .prefix(game_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (player, amount) = item?;
+            Ok(BetTotal { player, amount })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(BetsResponse {
+        open: betting_open(&game),
+        totals,
+    })
+}
+
+pub fn get_bet(deps: Deps, game_id: u64, bettor: String) -> StdResult<BetResponse> {
+    let bettor = deps.api.addr_validate(&bettor)?;
+    let bet = BETS.load(deps.storage, (game_id, &bettor))?;
+    Ok(BetResponse { player: bet.player, amount: bet.amount })
+}
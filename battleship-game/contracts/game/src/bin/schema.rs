@@ -1,3 +1,12 @@
-pub fn main() {
-    
-}
\ No newline at end of file
+use cosmwasm_schema::write_api;
+
+use battleship_game::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+fn main() {
+    write_api! {
+        instantiate: InstantiateMsg,
+        execute: ExecuteMsg,
+        query: QueryMsg,
+        migrate: MigrateMsg,
+    }
+}
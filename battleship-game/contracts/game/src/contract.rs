@@ -1,389 +1,2205 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128
+    to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128
 };
 
+use cw2::{ensure_from_older_version, set_contract_version};
+
 use crate::{
-    msg::{ExecuteMsg, InstantiateMsg, QueryMsg}, 
-    state::{Board, GameConfig, GameState, Player, GAME_CONFIG, GAME_STATE, MIN_STAKE, PLAYERS}, ContractError
+    msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, Proof, QueryMsg}, 
+    state::{
+        Config, FeeRoute, Game, GameConfig, GameState, Player, ProofFormat, Salvo, StakeAsset, Weapons, CONFIG,
+        DEFAULT_BETTING_MOVES, DEFAULT_FEE_PERCENTAGE,
+        DEFAULT_MIN_STAKE, DEFAULT_REWARD_PERCENTAGE, DEFAULT_TURN_DURATION, GAMES, NEXT_GAME_ID, OPEN_GAMES, PLAYERS
+    },
+    betting, tournament, ContractError
 };
 
+// version info for migration
+const CONTRACT_NAME: &str = "crates.io:battleship-game";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// games read by a single migration
+const MAX_MIGRATE_LIMIT: u32 = 500;
+const DEFAULT_MIGRATE_LIMIT: u32 = 100;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg
 ) -> Result<Response, ContractError> {
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender,
+    };
+    let config = Config {
+        admin,
+        token_address: deps.api.addr_validate(&msg.token_address)?,
+        min_stake: msg.min_stake.unwrap_or(Uint128::new(DEFAULT_MIN_STAKE)),
+        fee_percentage: msg.fee_percentage.unwrap_or(DEFAULT_FEE_PERCENTAGE),
+        reward_percentage: msg.reward_percentage.unwrap_or(DEFAULT_REWARD_PERCENTAGE),
+        turn_duration: msg.turn_duration.unwrap_or(DEFAULT_TURN_DURATION),
+        fee_route: msg.fee_route.unwrap_or(FeeRoute::Accrue),
+        treasury: msg.treasury
+            .map(|treasury| deps.api.addr_validate(&treasury))
+            .transpose()?,
+        native_denom: msg.native_denom,
+        native_min_stake: msg.native_min_stake.unwrap_or_default(),
+        betting_moves: msg.betting_moves.unwrap_or(DEFAULT_BETTING_MOVES),
+    };
+    execute::validate_config(&config)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    CONFIG.save(deps.storage, &config)?;
+    NEXT_GAME_ID.save(deps.storage, &1u64)?;
+
+    Ok(Response::new())
+}
 
-    let ships = msg.ships;
-    if ships == 0 {
-        return Err(ContractError::InvalidShips {});
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateGame {
+            stake,
+            fleet,
+            width,
+            height,
+            turn_duration,
+            board,
+            invitee,
+            min_rating,
+            max_rating,
+            token_address,
+            denom,
+            proof_format,
+            salvo,
+            extra_turn,
+            weapons
+        } => execute::create_game(
+            deps,
+            info,
+            stake,
+            fleet,
+            width,
+            height,
+            turn_duration,
+            board,
+            invitee,
+            min_rating,
+            max_rating,
+            token_address,
+            denom,
+            proof_format,
+            salvo,
+            extra_turn,
+            weapons
+        ),
+        ExecuteMsg::JoinGame { game_id, board } =>
+            execute::join_with_funds(deps, env, info, game_id, board),
+        ExecuteMsg::Receive(msg) =>
+            execute::receive(deps, env, info, msg),
+        ExecuteMsg::Deposit { game_id } => {
+            let events = execute::deposit_funds(deps, env, &info, game_id)?;
+            Ok(Response::new()
+                .add_attribute("action", "deposit")
+                .add_attribute("game_id", game_id.to_string())
+                .add_events(events)
+            )
+        },
+        ExecuteMsg::Withdraw { game_id } =>
+            execute::withdraw(deps, info, game_id),
+        ExecuteMsg::Play { game_id, field, value, salt, proof } => 
+            execute::play(deps, env, info, game_id, field, value, salt, proof),
+        ExecuteMsg::PlaySalvo { game_id, shots } =>
+            execute::play_salvo(deps, env, info, game_id, shots),
+        ExecuteMsg::Bomb { game_id, center, shots } =>
+            execute::bomb(deps, env, info, game_id, center, shots),
+        ExecuteMsg::Radar { game_id, segment } =>
+            execute::radar(deps, env, info, game_id, segment),
+        ExecuteMsg::AnswerScan { game_id, found } =>
+            execute::answer_scan(deps, env, info, game_id, found),
+        ExecuteMsg::Fire { game_id, field } =>
+            execute::fire(deps, env, info, game_id, field),
+        ExecuteMsg::Answer { game_id, value, salt, proof } =>
+            execute::answer(deps, env, info, game_id, value, salt, proof),
+        ExecuteMsg::TimeoutWin { game_id } => 
+            execute::timeout_win(deps, env, info, game_id),
+        ExecuteMsg::Resign { game_id } =>
+            execute::resign(deps, env, info, game_id),
+        ExecuteMsg::OfferDraw { game_id } =>
+            execute::offer_draw(deps, info, game_id),
+        ExecuteMsg::AcceptDraw { game_id } =>
+            execute::accept_draw(deps, info, game_id),
+        ExecuteMsg::Cancel { game_id } =>
+            execute::cancel(deps, info, game_id),
+        ExecuteMsg::Reveal { game_id, board, salts } =>
+            execute::reveal(deps, env, info, game_id, board, salts),
+        ExecuteMsg::Settle { game_id } =>
+            execute::settle(deps, env, game_id),
+        ExecuteMsg::UpdateConfig {
+            admin,
+            min_stake,
+            fee_percentage,
+            reward_percentage,
+            turn_duration,
+            fee_route,
+            treasury,
+            native_denom,
+            native_min_stake,
+            betting_moves
+        } => execute::update_config(
+            deps,
+            info,
+            admin,
+            min_stake,
+            fee_percentage,
+            reward_percentage,
+            turn_duration,
+            fee_route,
+            treasury,
+            native_denom,
+            native_min_stake,
+            betting_moves
+        ),
+        ExecuteMsg::ClaimBet { game_id } =>
+            betting::claim_bet(deps, info, game_id),
+        ExecuteMsg::AddToken { token_address, min_stake, mint_reward } =>
+            execute::add_token(deps, info, token_address, min_stake, mint_reward),
+        ExecuteMsg::RemoveToken { token_address } =>
+            execute::remove_token(deps, info, token_address),
+        ExecuteMsg::WithdrawFees { token_address, denom, recipient, amount } =>
+            execute::withdraw_fees(deps, info, token_address, denom, recipient, amount),
+        ExecuteMsg::CreateTournament {
+            entry_fee,
+            max_players,
+            prizes,
+            fleet,
+            width,
+            height,
+            turn_duration
+        } => tournament::create_tournament(
+            deps,
+            info,
+            entry_fee,
+            max_players,
+            prizes,
+            fleet,
+            width,
+            height,
+            turn_duration
+        ),
+        ExecuteMsg::StartTournament { tournament_id } =>
+            tournament::start_tournament(deps, env, info, tournament_id),
+        ExecuteMsg::CancelTournament { tournament_id } =>
+            tournament::cancel_tournament(deps, info, tournament_id),
+        ExecuteMsg::CommitBoard { game_id, board } =>
+            tournament::commit_board(deps, env, info, game_id, board),
+        ExecuteMsg::ExpireMatch { game_id } =>
+            tournament::expire_match(deps, env, game_id),
     }
-    let game_config = GameConfig { 
-        token_address: deps.api.addr_validate(&msg.token_address)?, 
-        ships: ships 
-    };
-    GAME_CONFIG.save(deps.storage, &game_config)?;
+}
 
-    let game_state = GameState { 
-        started: false, 
-        finished: false, 
-        turn: deps.api.addr_validate(&msg.players[0].address)?, 
-        last_turn_time: 0 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(
+    deps: Deps,
+    _env: Env,
+    msg: QueryMsg
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetPlayers { game_id } => to_json_binary(&query::get_players(deps, game_id)?),
+        QueryMsg::GetGameConfig { game_id } => to_json_binary(&query::get_game_config(deps, game_id)?),
+        QueryMsg::GetGameState { game_id } => to_json_binary(&query::get_game_state(deps, game_id)?),
+        QueryMsg::GetConfig {} => to_json_binary(&query::get_config(deps)?),
+        QueryMsg::GetAccruedFees {} => to_json_binary(&query::get_accrued_fees(deps)?),
+        QueryMsg::GetBoardView { game_id, player } =>
+            to_json_binary(&query::get_board_view(deps, game_id, player)?),
+        QueryMsg::GetMoves { game_id, start_after, limit } =>
+            to_json_binary(&query::get_moves(deps, game_id, start_after, limit)?),
+        QueryMsg::GetRating { address } =>
+            to_json_binary(&query::get_rating(deps, address)?),
+        QueryMsg::GetPlayerStats { address } =>
+            to_json_binary(&query::get_player_stats(deps, address)?),
+        QueryMsg::Leaderboard { sort_by, start_after, limit } =>
+            to_json_binary(&query::leaderboard(deps, sort_by, start_after, limit)?),
+        QueryMsg::GetOpenGames { start_after, limit } =>
+            to_json_binary(&query::get_open_games(deps, start_after, limit)?),
+        QueryMsg::GetAcceptedTokens { start_after, limit } =>
+            to_json_binary(&query::get_accepted_tokens(deps, start_after, limit)?),
+        QueryMsg::GetBets { game_id } =>
+            to_json_binary(&betting::get_bets(deps, game_id)?),
+        QueryMsg::GetBet { game_id, bettor } =>
+            to_json_binary(&betting::get_bet(deps, game_id, bettor)?),
+        QueryMsg::GetTournament { tournament_id } =>
+            to_json_binary(&tournament::get_tournament(deps, tournament_id)?),
+    }
+}
+
+/// Game as stored by any version, games created before stakes could be native
+/// were all staked in the token at `token_address`.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(untagged)]
+enum StoredGame {
+    Current(Game),
+    Legacy { config: LegacyGameConfig, state: GameState },
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+struct LegacyGameConfig {
+    token_address: Addr,
+    creator: Addr,
+    invitee: Option<Addr>,
+    stake: Uint128,
+    fleet: Vec<usize>,
+    width: usize,
+    height: usize,
+    turn_duration: u64,
+    fee_percentage: u64,
+    reward_percentage: u64,
+    #[serde(default)]
+    min_rating: Option<u32>,
+    #[serde(default)]
+    max_rating: Option<u32>,
+}
+
+impl From<StoredGame> for Game {
+    fn from(game: StoredGame) -> Self {
+        match game {
+            StoredGame::Current(game) => game,
+            StoredGame::Legacy { config, state } => Game {
+                config: GameConfig {
+                    asset: StakeAsset::Cw20 { address: config.token_address },
+                    creator: config.creator,
+                    invitee: config.invitee,
+                    stake: config.stake,
+                    fleet: config.fleet,
+                    width: config.width,
+                    height: config.height,
+                    turn_duration: config.turn_duration,
+                    fee_percentage: config.fee_percentage,
+                    reward_percentage: config.reward_percentage,
+                    min_rating: config.min_rating,
+                    max_rating: config.max_rating,
+                    tournament: None,
+                    proof_format: ProofFormat::Hex,
+                    salvo: None,
+                    extra_turn: false,
+                    weapons: Weapons::default(),
+                    betting_moves: DEFAULT_BETTING_MOVES,
+                },
+                state,
+            },
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let original_version =
+        ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let version: semver::Version = CONTRACT_VERSION
+        .parse()
+        .map_err(|_| StdError::generic_err("Invalid contract version"))?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", original_version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION);
+
+    // later batches run against the already bumped version
+    if original_version < version || msg.start_after.is_some() {
+        // fields added since are filled in by their serde defaults when loaded, only
+        // games staked before native stakes existed are stored in a different layout
+        let limit = msg.limit.unwrap_or(DEFAULT_MIGRATE_LIMIT).min(MAX_MIGRATE_LIMIT) as usize;
+        let batch = cw_storage_plus::Map::<u64, StoredGame>::new("games")
+            .range(
+                deps.storage,
+                msg.start_after.map(cw_storage_plus::Bound::exclusive),
+                None,
+                cosmwasm_std::Order::Ascending
+            )
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut migrated = 0;
+        for (game_id, game) in &batch {
+            if !matches!(game, StoredGame::Current(_)) {
+                GAMES.save(deps.storage, *game_id, &game.clone().into())?;
+                migrated += 1;
+            }
+        }
+
+        response = response.add_attribute("migrated_games", migrated.to_string());
+        // a full batch may be followed by more games, migrate again starting after it
+        if batch.len() == limit {
+            if let Some((game_id, _)) = batch.last() {
+                response = response.add_attribute("last_game_id", game_id.to_string());
+            }
+        }
+    }
+
+    Ok(response)
+}
+
+pub(crate) mod execute {
+    use cosmwasm_std::{coins, from_json, Addr, BankMsg, CosmosMsg, Decimal, Empty, Event, Int128, Order, Storage};
+    use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+    use cw_utils::must_pay;
+    use board_commitment::{BoardCommitment, Encoding, Node};
+
+    use crate::msg::{ReceiveMsg, Shot};
+    use crate::state::{
+        player_stats, AcceptedToken, Board, Game, Move, Rating, Scan, Segment, StakeAsset, ACCEPTED_TOKENS, ACCRUED_FEES,
+        ACCRUED_NATIVE_FEES, BETS, K_FACTOR, MAX_BOARD_SIDE, MIN_SALT_LENGTH, MIN_TURN_DURATION, MOVES, PLACEMENT_BUDGET,
+        PROVISIONAL_GAMES, PROVISIONAL_K_FACTOR, RATINGS, REVEAL_DURATION, SHOTS
     };
-    GAME_STATE.save(deps.storage, &game_state)?;
 
-    if msg.players[0].stake < Uint128::new(MIN_STAKE) {
-        return Err(ContractError::InvalidStake {})
+    use super::*;
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_game(
+        deps: DepsMut,
+        info: MessageInfo,
+        stake: Uint128,
+        fleet: Vec<usize>,
+        width: usize,
+        height: usize,
+        turn_duration: Option<u64>,
+        board: String,
+        invitee: Option<String>,
+        min_rating: Option<u32>,
+        max_rating: Option<u32>,
+        token_address: Option<String>,
+        denom: Option<String>,
+        proof_format: Option<ProofFormat>,
+        salvo: Option<Salvo>,
+        extra_turn: Option<bool>,
+        weapons: Option<Weapons>
+    ) -> Result<Response, ContractError> {
+        validate_rules(&fleet, width, height)?;
+        let board = normalize_root(&board)?;
+        if let Some(Salvo::Fixed(count)) = salvo {
+            if count == 0 || count as usize > width * height {
+                return Err(ContractError::InvalidSalvo {});
+            }
+        }
+        let config = CONFIG.load(deps.storage)?;
+
+        let token_address = token_address
+            .map(|token_address| deps.api.addr_validate(&token_address))
+            .transpose()?;
+
+        // no reward can be minted in a bank denom or a token the contract is not the minter of
+        let (asset, min_stake, reward_percentage) = match (token_address, denom) {
+            (Some(_), Some(_)) => return Err(ContractError::InvalidToken {}),
+            (None, Some(denom)) if config.native_denom.as_ref() == Some(&denom) =>
+                (StakeAsset::Native { denom }, config.native_min_stake, 0),
+            (None, Some(_)) => return Err(ContractError::InvalidToken {}),
+            (Some(address), None) if address != config.token_address => {
+                let token = ACCEPTED_TOKENS
+                    .may_load(deps.storage, &address)?
+                    .ok_or(ContractError::TokenNotAccepted {})?;
+                let reward_percentage = if token.mint_reward { config.reward_percentage } else { 0 };
+                (StakeAsset::Cw20 { address }, token.min_stake, reward_percentage)
+            }
+            _ => (StakeAsset::Cw20 { address: config.token_address }, config.min_stake, config.reward_percentage),
+        };
+        if stake < min_stake {
+            return Err(ContractError::InvalidStake {})
+        }
+
+        let turn_duration = turn_duration.unwrap_or(config.turn_duration);
+        if turn_duration < MIN_TURN_DURATION {
+            return Err(ContractError::InvalidTurnDuration {});
+        }
+
+        let invitee = invitee
+            .map(|invitee| deps.api.addr_validate(&invitee))
+            .transpose()?;
+        if invitee.as_ref() == Some(&info.sender) {
+            return Err(ContractError::InvalidPlayers {});
+        }
+
+        if let (Some(min_rating), Some(max_rating)) = (min_rating, max_rating) {
+            if min_rating > max_rating {
+                return Err(ContractError::RatingOutOfRange {});
+            }
+        }
+
+        let game_id = NEXT_GAME_ID.load(deps.storage)?;
+        NEXT_GAME_ID.save(deps.storage, &(game_id + 1))?;
+
+        let game = Game {
+            config: GameConfig { 
+                asset: asset.clone(),
+                creator: info.sender.clone(),
+                invitee,
+                stake,
+                fleet,
+                width,
+                height,
+                turn_duration,
+                fee_percentage: config.fee_percentage,
+                reward_percentage,
+                min_rating,
+                max_rating,
+                tournament: None,
+                proof_format: proof_format.unwrap_or_default(),
+                salvo,
+                extra_turn: extra_turn.unwrap_or(false),
+                weapons: weapons.unwrap_or_default(),
+                betting_moves: config.betting_moves,
+            },
+            state: GameState { 
+                started: false, 
+                finished: false, 
+                turn: info.sender.clone(), 
+                last_turn_time: 0,
+                pending_shot: None,
+                pending_scan: None,
+                draw_offer: None,
+                moves: 0,
+                winner: None,
+                reveal_deadline: 0,
+                settled: false,
+                timed_out: false,
+                paid_to: None,
+            },
+        };
+        GAMES.save(deps.storage, game_id, &game)?;
+        OPEN_GAMES.save(deps.storage, game_id, &Empty {})?;
+
+        let player = Player {
+            address: info.sender.clone(),
+            stake,
+            deposited: false,
+            board: Board {
+                fields: board,
+                sank: vec![],
+                revealed: None,
+            },
+            weapons_used: Weapons::default(),
+            scans: vec![],
+        };
+        PLAYERS.save(deps.storage, (game_id, &info.sender), &player)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "create_game")
+            .add_attribute("game_id", game_id.to_string())
+            .add_attribute("creator", info.sender.to_string())
+            .add_attribute("asset", asset.to_string())
+            .add_attribute("stake", stake.to_string())
+        )
+    }
+
+    /// Checks the board dimensions and that the fleet can be placed on the board.
+    pub fn validate_rules(fleet: &[usize], width: usize, height: usize) -> Result<(), ContractError> {
+        if width == 0 || height == 0 || width > MAX_BOARD_SIDE || height > MAX_BOARD_SIDE {
+            return Err(ContractError::InvalidBoard {});
+        }
+        // every ship has to fit in a row or a column, and the fleet on the board
+        let fleet_size: usize = fleet.iter().sum();
+        if fleet.is_empty()
+            || fleet.iter().any(|&length| length == 0 || length > width.max(height))
+            || fleet_size > width * height {
+            return Err(ContractError::InvalidShips {});
+        }
+        // otherwise no board could ever be revealed as legal and both players would be slashed
+        if !fleet_fits(fleet, width, height) {
+            return Err(ContractError::InvalidShips {});
+        }
+        Ok(())
+    }
+
+    /// Whether the ships of `fleet` can be placed without touching each other,
+    /// searched by backtracking. Fleets that cannot be placed within
+    /// `PLACEMENT_BUDGET` tried positions are treated as not fitting.
+    fn fleet_fits(fleet: &[usize], width: usize, height: usize) -> bool {
+        let mut ships = fleet.to_vec();
+        ships.sort_unstable_by(|a, b| b.cmp(a));
+        let mut board = vec![vec![false; width]; height];
+        let mut budget = PLACEMENT_BUDGET;
+        place_ships(&ships, &mut board, 0, &mut budget)
+    }
+
+    fn place_ships(ships: &[usize], board: &mut [Vec<bool>], from: usize, budget: &mut u32) -> bool {
+        let Some((&length, rest)) = ships.split_first() else {
+            return true;
+        };
+        let (height, width) = (board.len(), board[0].len());
+        let occupied = |board: &[Vec<bool>], (row, column): (usize, usize)| {
+            board.get(row).and_then(|cells| cells.get(column)).copied().unwrap_or(false)
+        };
+
+        for start in from..width * height {
+            for vertical in [false, true] {
+                if vertical && length == 1 {
+                    continue;
+                }
+                if *budget == 0 {
+                    return false;
+                }
+                *budget -= 1;
+
+                let (row, column) = (start / width, start % width);
+                let cells: Vec<_> = (0..length)
+                    .map(|offset| if vertical { (row + offset, column) } else { (row, column + offset) })
+                    .collect();
+                let free = cells.iter().all(|&(r, c)| {
+                    r < height && c < width && !board[r][c]
+                        && neighbours((r, c)).iter().all(|&field| !occupied(board, field))
+                });
+                if !free {
+                    continue;
+                }
+
+                for &(r, c) in &cells {
+                    board[r][c] = true;
+                }
+                // equal ships are placed in order, swapping them gives the same board
+                let next = if rest.first() == Some(&length) { start + 1 } else { 0 };
+                if place_ships(rest, board, next, budget) {
+                    return true;
+                }
+                for &(r, c) in &cells {
+                    board[r][c] = false;
+                }
+            }
+        }
+        false
+    }
+
+    /// Checks that a committed root is a single hex encoded node and returns it
+    /// in the lowercase form the revealed board is compared with.
+    pub fn normalize_root(board: &str) -> Result<String, ContractError> {
+        board_commitment::decode_node(board)
+            .map(|node| board_commitment::encode_node(&node))
+            .ok_or(ContractError::InvalidRoot {})
+    }
+
+    pub fn join_game(
+        deps: DepsMut,
+        sender: Addr,
+        game_id: u64,
+        board: String
+    ) -> Result<Response, ContractError> {
+        let board = normalize_root(&board)?;
+        let game = GAMES.load(deps.storage, game_id)?;
+
+        if !OPEN_GAMES.has(deps.storage, game_id) {
+            return Err(ContractError::GameNotOpen {});
+        }
+
+        if sender == game.config.creator {
+            return Err(ContractError::InvalidPlayers {});
+        }
+
+        // a spectator who bet on the creator would be betting against themselves
+        if BETS.has(deps.storage, (game_id, &sender)) {
+            return Err(ContractError::BetAgainstSelf {});
+        }
+
+        if let Some(invitee) = &game.config.invitee {
+            if *invitee != sender {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
+
+        let rating = RATINGS.may_load(deps.storage, &sender)?.unwrap_or_default().rating;
+        if game.config.min_rating.is_some_and(|min_rating| rating < min_rating)
+            || game.config.max_rating.is_some_and(|max_rating| rating > max_rating) {
+            return Err(ContractError::RatingOutOfRange {});
+        }
+
+        let player = Player {
+            address: sender.clone(),
+            stake: game.config.stake,
+            deposited: false,
+            board: Board {
+                fields: board,
+                sank: vec![],
+                revealed: None,
+            },
+            weapons_used: Weapons::default(),
+            scans: vec![],
+        };
+        PLAYERS.save(deps.storage, (game_id, &sender), &player)?;
+        OPEN_GAMES.remove(deps.storage, game_id);
+
+        Ok(Response::new()
+            .add_attribute("action", "join_game")
+            .add_attribute("game_id", game_id.to_string())
+            .add_attribute("player", sender.to_string())
+        )
+    }
+
+    /// Joins the game, escrowing the stake of a native game if it was sent along.
+    pub fn join_with_funds(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        game_id: u64,
+        board: String
+    ) -> Result<Response, ContractError> {
+        let response = join_game(deps.branch(), info.sender.clone(), game_id, board)?;
+        if info.funds.is_empty() {
+            return Ok(response);
+        }
+
+        let events = deposit_funds(deps, env, &info, game_id)?;
+        Ok(response.add_events(events))
+    }
+
+    /// Loads the other participant of the game.
+    pub fn load_opponent(
+        deps: Deps,
+        game_id: u64,
+        player: &Addr
+    ) -> Result<Player, ContractError> {
+        PLAYERS
+            .prefix(game_id)
+            .range(deps.storage, None, None, Order::Ascending)
+            .find_map(|item| {
+                let (addr, player_data) = item.ok()?;
+                if addr != *player {
+                    Some(player_data)
+                } else {
+                    None
+                }
+            })
+            .ok_or(ContractError::PlayerNotFound {  })
+    }
+
+    pub fn receive(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        wrapper: Cw20ReceiveMsg
+    ) -> Result<Response, ContractError> {
+        let sender = deps.api.addr_validate(&wrapper.sender)?;
+        let amount = wrapper.amount;
+
+        match from_json(&wrapper.msg)? {
+            ReceiveMsg::JoinGame { game_id, board } => {
+                let response = join_game(deps.branch(), sender.clone(), game_id, board)?;
+                let asset = StakeAsset::Cw20 { address: info.sender };
+                let events = deposit(deps, env, &asset, sender, game_id, amount)?;
+                Ok(response.add_events(events))
+            },
+            ReceiveMsg::Deposit { game_id } => {
+                let asset = StakeAsset::Cw20 { address: info.sender.clone() };
+                let events = deposit(deps, env, &asset, sender, game_id, amount)?;
+                Ok(Response::new()
+                    .add_attribute("action", "deposit")
+                    .add_attribute("game_id", game_id.to_string())
+                    .add_events(events)
+                )
+            },
+            ReceiveMsg::RegisterTournament { tournament_id } =>
+                tournament::register(deps, &info.sender, sender, tournament_id, amount),
+            ReceiveMsg::PlaceBet { game_id, player } => {
+                let asset = StakeAsset::Cw20 { address: info.sender };
+                betting::place_bet(deps, &asset, sender, game_id, player, amount)
+            },
+        }
+    }
+
+    /// Escrows the stake of a native game sent along with the message.
+    pub fn deposit_funds(
+        deps: DepsMut,
+        env: Env,
+        info: &MessageInfo,
+        game_id: u64
+    ) -> Result<Vec<Event>, ContractError> {
+        let asset = GAMES.load(deps.storage, game_id)?.config.asset;
+        let amount = match &asset {
+            StakeAsset::Native { denom } => must_pay(info, denom)?,
+            StakeAsset::Cw20 { .. } => return Err(ContractError::InvalidToken {}),
+        };
+
+        deposit(deps, env, &asset, info.sender.clone(), game_id, amount)
+    }
+
+    /// Escrows a player's stake sent through the token contract or along with
+    /// the message. The game starts as soon as the second player's stake is in.
+    pub fn deposit(
+        deps: DepsMut,
+        env: Env,
+        asset: &StakeAsset,
+        sender: Addr,
+        game_id: u64,
+        amount: Uint128
+    ) -> Result<Vec<Event>, ContractError> {
+        let mut game = GAMES.load(deps.storage, game_id)?;
+
+        if *asset != game.config.asset {
+            return Err(ContractError::InvalidToken {});
+        }
+
+        if game.state.started {
+            return Err(ContractError::GameStarted {});
+        }
+
+        if game.state.finished {
+            return Err(ContractError::GameFinished {});
+        }
+
+        let mut player = PLAYERS
+            .may_load(deps.storage, (game_id, &sender))?
+            .ok_or(ContractError::Unauthorized {})?;
+
+        if player.deposited {
+            return Err(ContractError::AlreadyDeposited {});
+        }
+
+        if amount != player.stake {
+            return Err(ContractError::InvalidStake {});
+        }
+
+        player.deposited = true;
+        PLAYERS.save(deps.storage, (game_id, &sender), &player)?;
+
+        let mut events = vec![
+            Event::new("deposit")
+                .add_attribute("game_id", game_id.to_string())
+                .add_attribute("player", sender.to_string())
+                .add_attribute("amount", amount.to_string())
+        ];
+
+        let opponent = load_opponent(deps.as_ref(), game_id, &sender).ok();
+        if opponent.is_some_and(|opponent| opponent.deposited) {
+            game.state.started = true;
+            game.state.last_turn_time = env.block.time.seconds();
+            GAMES.save(deps.storage, game_id, &game)?;
+
+            events.push(
+                Event::new("game_started")
+                    .add_attribute("game_id", game_id.to_string())
+                    .add_attribute("stake", player.stake.to_string())
+            );
+        }
+
+        Ok(events)
+    }
+
+    pub fn withdraw(
+        deps: DepsMut,
+        info: MessageInfo,
+        game_id: u64
+    ) -> Result<Response, ContractError> {
+        let game = GAMES.load(deps.storage, game_id)?;
+
+        if game.state.started {
+            return Err(ContractError::GameStarted {});
+        }
+
+        let mut player = PLAYERS
+            .may_load(deps.storage, (game_id, &info.sender))?
+            .ok_or(ContractError::Unauthorized {})?;
+
+        if !player.deposited {
+            return Err(ContractError::NoDeposit {});
+        }
+
+        player.deposited = false;
+        PLAYERS.save(deps.storage, (game_id, &info.sender), &player)?;
+
+        let transfer_msg = transfer(
+            player.address.clone(),
+            player.stake,
+            &game.config.asset
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "withdraw")
+            .add_attribute("game_id", game_id.to_string())
+            .add_attribute("player", player.address.to_string())
+            .add_attribute("amount", player.stake.to_string())
+            .add_message(transfer_msg)
+        )
+    }
+
+    /// Checks that the game is running and `sender` is the player to move
+    /// before their turn expired.
+    pub fn ensure_turn(game: &Game, env: &Env, sender: &Addr) -> Result<(), ContractError> {
+        if !game.state.started {
+            return Err(ContractError::GameNotStarted {});
+        }
+
+        if game.state.finished {
+            return Err(ContractError::GameFinished {});
+        }
+
+        if env.block.time.seconds() > game.state.last_turn_time + game.config.turn_duration {
+            return Err(ContractError::TurnExpired {  });
+        }
+
+        if *sender != game.state.turn {
+            return Err(ContractError::WrongTurn {  })
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn play(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        game_id: u64,
+        field: (usize, usize),
+        field_value: bool,
+        salt: String,
+        proof: Proof
+    ) -> Result<Response, ContractError> {
+        let game = GAMES.load(deps.storage, game_id)?;
+        ensure_turn(&game, &env, &info.sender)?;
+
+        if game.config.salvo.is_some() {
+            return Err(ContractError::SalvoGame {});
+        }
+
+        ensure_nothing_pending(&game)?;
+
+        let player = PLAYERS.load(deps.storage, (game_id, &info.sender))?;
+        let opponent = load_opponent(deps.as_ref(), game_id, &player.address)?;
+
+        let shot = Shot { field, value: field_value, salt, proof };
+        check_shot(deps.storage, &game, game_id, &player.address, &opponent, shot)?;
+
+        resolve_shot(deps, env, game, game_id, "play", player, opponent, field, field_value)
+    }
+
+    /// Fires every shot of a salvo game's turn at once.
+    pub fn play_salvo(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        game_id: u64,
+        shots: Vec<Shot>
+    ) -> Result<Response, ContractError> {
+        let game = GAMES.load(deps.storage, game_id)?;
+        ensure_turn(&game, &env, &info.sender)?;
+        ensure_nothing_pending(&game)?;
+
+        let shooter = PLAYERS.load(deps.storage, (game_id, &info.sender))?;
+        let defender = load_opponent(deps.as_ref(), game_id, &shooter.address)?;
+
+        let allowed = match game.config.salvo {
+            Some(Salvo::Fixed(count)) => count as usize,
+            Some(Salvo::Ships) => {
+                super::query::board_view(deps.as_ref(), &game.config, game_id, &defender.address, &shooter)?
+                    .remaining_ships
+            }
+            None => return Err(ContractError::NotSalvoGame {}),
+        };
+        if shots.is_empty() || shots.len() > allowed {
+            return Err(ContractError::InvalidSalvo {});
+        }
+
+        resolve_shots(deps, env, game, game_id, "play_salvo", shooter, defender, shots)
+    }
+
+    /// Uses a bomb on the 3x3 area around `center`. Every field of the area on
+    /// the board and not fired at before has to be proven, and is resolved
+    /// like a shot of a salvo.
+    pub fn bomb(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        game_id: u64,
+        center: (usize, usize),
+        shots: Vec<Shot>
+    ) -> Result<Response, ContractError> {
+        let game = GAMES.load(deps.storage, game_id)?;
+        ensure_turn(&game, &env, &info.sender)?;
+        ensure_nothing_pending(&game)?;
+
+        let mut shooter = PLAYERS.load(deps.storage, (game_id, &info.sender))?;
+        let defender = load_opponent(deps.as_ref(), game_id, &shooter.address)?;
+
+        if shooter.weapons_used.bombs >= game.config.weapons.bombs {
+            return Err(ContractError::NoWeaponUses {});
+        }
+        if !game.config.contains(center) {
+            return Err(ContractError::InvalidField {  });
+        }
+
+        let mut area = vec![];
+        for row in center.0.saturating_sub(1)..=center.0 + 1 {
+            for column in center.1.saturating_sub(1)..=center.1 + 1 {
+                let field = (row, column);
+                if game.config.contains(field)
+                    && !SHOTS.has(deps.storage, (game_id, &shooter.address, game.config.index(field))) {
+                    area.push(field);
+                }
+            }
+        }
+        let mut targets: Vec<_> = shots.iter().map(|shot| shot.field).collect();
+        targets.sort_unstable();
+        if area.is_empty() || targets != area {
+            return Err(ContractError::InvalidTarget {});
+        }
+
+        shooter.weapons_used.bombs += 1;
+        PLAYERS.save(deps.storage, (game_id, &shooter.address), &shooter)?;
+
+        resolve_shots(deps, env, game, game_id, "bomb", shooter, defender, shots)
+    }
+
+    /// Uses a radar on a row or column segment. The opponent answers whether
+    /// any field of it holds a ship without proving single fields, the answer
+    /// is checked against their board once it is revealed after the game.
+    pub fn radar(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        game_id: u64,
+        segment: Segment
+    ) -> Result<Response, ContractError> {
+        let mut game = GAMES.load(deps.storage, game_id)?;
+        ensure_turn(&game, &env, &info.sender)?;
+        ensure_nothing_pending(&game)?;
+
+        let mut scanner = PLAYERS.load(deps.storage, (game_id, &info.sender))?;
+        let defender = load_opponent(deps.as_ref(), game_id, &scanner.address)?;
+
+        if scanner.weapons_used.radars >= game.config.weapons.radars {
+            return Err(ContractError::NoWeaponUses {});
+        }
+
+        // bounded before listing the fields, so a huge length cannot exhaust the gas
+        let max_length = game.config.width.max(game.config.height);
+        if segment.length == 0 || segment.length > max_length || !game.config.contains(segment.start) {
+            return Err(ContractError::InvalidField {  });
+        }
+        if !segment.fields().iter().all(|&field| game.config.contains(field)) {
+            return Err(ContractError::InvalidField {  });
+        }
+
+        scanner.weapons_used.radars += 1;
+        PLAYERS.save(deps.storage, (game_id, &scanner.address), &scanner)?;
+
+        game.state.pending_scan = Some(segment.clone());
+        game.state.turn = defender.address;
+        game.state.last_turn_time = env.block.time.seconds();
+        GAMES.save(deps.storage, game_id, &game)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "radar")
+            .add_attribute("game_id", game_id.to_string())
+            .add_event(
+                Event::new("radar_fired")
+                    .add_attribute("scanner", scanner.address.to_string())
+                    .add_attribute("start", format!("{:?}", segment.start))
+                    .add_attribute("length", segment.length.to_string())
+                    .add_attribute("vertical", segment.vertical.to_string())
+            )
+        )
+    }
+
+    /// Answers the pending radar scan. The answer is kept with the defender
+    /// and the defender moves next, as after a shot.
+    pub fn answer_scan(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        game_id: u64,
+        found: bool
+    ) -> Result<Response, ContractError> {
+        let mut game = GAMES.load(deps.storage, game_id)?;
+        ensure_turn(&game, &env, &info.sender)?;
+
+        let segment = game.state.pending_scan.take().ok_or(ContractError::NoPendingScan {})?;
+
+        let mut defender = PLAYERS.load(deps.storage, (game_id, &info.sender))?;
+        defender.scans.push(Scan { segment: segment.clone(), found });
+        PLAYERS.save(deps.storage, (game_id, &defender.address), &defender)?;
+
+        game.state.last_turn_time = env.block.time.seconds();
+        GAMES.save(deps.storage, game_id, &game)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "answer_scan")
+            .add_attribute("game_id", game_id.to_string())
+            .add_event(
+                Event::new("radar_scan")
+                    .add_attribute("defender", defender.address.to_string())
+                    .add_attribute("start", format!("{:?}", segment.start))
+                    .add_attribute("length", segment.length.to_string())
+                    .add_attribute("vertical", segment.vertical.to_string())
+                    .add_attribute("found", found.to_string())
+            )
+        )
+    }
+
+    /// Rejects a move while a shot or a radar scan waits for the opponent's answer.
+    fn ensure_nothing_pending(game: &Game) -> Result<(), ContractError> {
+        if game.state.pending_shot.is_some() || game.state.pending_scan.is_some() {
+            return Err(ContractError::ShotPending {  });
+        }
+        Ok(())
+    }
+
+    /// Proves and records several shots of one move before checking the win,
+    /// so the last of several hits can end the game.
+    #[allow(clippy::too_many_arguments)]
+    fn resolve_shots(
+        deps: DepsMut,
+        env: Env,
+        mut game: Game,
+        game_id: u64,
+        action: &str,
+        shooter: Player,
+        mut defender: Player,
+        shots: Vec<Shot>
+    ) -> Result<Response, ContractError> {
+        let now = env.block.time.seconds();
+        game.state.last_turn_time = now;
+
+        let mut events = vec![];
+        let mut last_field = None;
+        let mut hit_any = false;
+        for shot in shots {
+            let (field, hit) = (shot.field, shot.value);
+            check_shot(deps.storage, &game, game_id, &shooter.address, &defender, shot)?;
+            record_shot(deps.storage, now, &mut game, game_id, &shooter.address, &mut defender, field, hit)?;
+            events.push(shot_event(field, hit));
+            last_field = Some(field);
+            hit_any |= hit;
+        }
+        PLAYERS.save(deps.storage, (game_id, &defender.address), &defender)?;
+
+        let response = Response::new()
+            .add_attribute("action", action)
+            .add_attribute("game_id", game_id.to_string())
+            .add_events(events);
+
+        if defender.board.sank.len() == game.config.fleet_size() {
+            finish(&mut game, &shooter.address, now);
+            GAMES.save(deps.storage, game_id, &game)?;
+
+            return Ok(response
+                .add_attribute("winner", shooter.address.to_string())
+                .add_attribute("reveal_deadline", game.state.reveal_deadline.to_string())
+                .add_event(Event::new("game_won").add_attribute("sank", format!("{:?}", last_field.unwrap())))
+            );
+        }
+
+        Ok(response.add_events(next_turn(deps.storage, &mut game, game_id, &shooter, defender, hit_any)?))
+    }
+
+    /// Checks that a shot at `defender`'s board is on the board, proven, and
+    /// not fired before.
+    fn check_shot(
+        storage: &dyn Storage,
+        game: &Game,
+        game_id: u64,
+        shooter: &Addr,
+        defender: &Player,
+        shot: Shot
+    ) -> Result<(), ContractError> {
+        if !game.config.contains(shot.field) {
+            return Err(ContractError::InvalidField {  });
+        }
+
+        if shot.salt.len() < MIN_SALT_LENGTH {
+            return Err(ContractError::InvalidSalt {  });
+        }
+
+        if !verify_proof(&game.config, shot.field, shot.value, &shot.salt, shot.proof, &defender.board.fields) {
+            return Err(ContractError::InvalidProof {  });
+        }
+
+        if defender.board.sank.contains(&shot.field) {
+            return Err(ContractError::AlreadySunk {});
+        }
+
+        if SHOTS.has(storage, (game_id, shooter, game.config.index(shot.field))) {
+            return Err(ContractError::AlreadyFired {});
+        }
+
+        Ok(())
+    }
+
+    /// Records a shot at the opponent's board. The opponent has to answer it
+    /// with a proof for their own field before their turn expires.
+    pub fn fire(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        game_id: u64,
+        field: (usize, usize)
+    ) -> Result<Response, ContractError> {
+        let mut game = GAMES.load(deps.storage, game_id)?;
+        ensure_turn(&game, &env, &info.sender)?;
+
+        if game.config.salvo.is_some() {
+            return Err(ContractError::SalvoGame {});
+        }
+
+        ensure_nothing_pending(&game)?;
+
+        if !game.config.contains(field) {
+            return Err(ContractError::InvalidField {  });
+        }
+
+        let opponent = load_opponent(deps.as_ref(), game_id, &info.sender)?;
+        if opponent.board.sank.contains(&field) {
+            return Err(ContractError::AlreadySunk {});
+        }
+
+        if SHOTS.has(deps.storage, (game_id, &info.sender, game.config.index(field))) {
+            return Err(ContractError::AlreadyFired {});
+        }
+
+        game.state.pending_shot = Some(field);
+        game.state.turn = opponent.address.clone();
+        game.state.last_turn_time = env.block.time.seconds();
+        GAMES.save(deps.storage, game_id, &game)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "fire")
+            .add_attribute("game_id", game_id.to_string())
+            .add_event(
+                Event::new("shot_fired")
+                    .add_attribute("shooter", info.sender.to_string())
+                    .add_attribute("field", format!("{:?}", field))
+            )
+        )
+    }
+
+    /// Resolves the pending shot with a proof of the defender's own field.
+    pub fn answer(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        game_id: u64,
+        field_value: bool,
+        salt: String,
+        proof: Proof
+    ) -> Result<Response, ContractError> {
+        let mut game = GAMES.load(deps.storage, game_id)?;
+        ensure_turn(&game, &env, &info.sender)?;
+
+        let field = game.state.pending_shot.take().ok_or(ContractError::NoPendingShot {  })?;
+
+        let defender = PLAYERS.load(deps.storage, (game_id, &info.sender))?;
+        let shooter = load_opponent(deps.as_ref(), game_id, &defender.address)?;
+
+        if salt.len() < MIN_SALT_LENGTH {
+            return Err(ContractError::InvalidSalt {  });
+        }
+
+        if !verify_proof(&game.config, field, field_value, &salt, proof, &defender.board.fields) {
+            return Err(ContractError::InvalidProof {  });
+        }
+
+        resolve_shot(deps, env, game, game_id, "answer", shooter, defender, field, field_value)
+    }
+
+    /// Applies a proven shot to the defender's board, ends the game when the
+    /// whole fleet is hit, and otherwise passes the turn to the defender.
+    #[allow(clippy::too_many_arguments)]
+    pub fn resolve_shot(
+        deps: DepsMut,
+        env: Env,
+        mut game: Game,
+        game_id: u64,
+        action: &str,
+        shooter: Player,
+        mut defender: Player,
+        field: (usize, usize),
+        field_value: bool
+    ) -> Result<Response, ContractError> {
+        let now = env.block.time.seconds();
+        game.state.last_turn_time = now;
+
+        record_shot(deps.storage, now, &mut game, game_id, &shooter.address, &mut defender, field, field_value)?;
+        if field_value {
+            PLAYERS.save(deps.storage, (game_id, &defender.address), &defender)?;
+
+            if defender.board.sank.len() == game.config.fleet_size() {
+                finish(&mut game, &shooter.address, now);
+                GAMES.save(deps.storage, game_id, &game)?;
+
+                return Ok(Response::new()
+                    .add_attribute("action", action)
+                    .add_attribute("game_id", game_id.to_string())
+                    .add_attribute("winner", shooter.address.to_string())
+                    .add_attribute("reveal_deadline", game.state.reveal_deadline.to_string())
+                    .add_event(Event::new("game_won").add_attribute("sank", format!("{:?}", field)))
+                );
+            }
+        }
+
+        let response = Response::new()
+            .add_attribute("action", action)
+            .add_attribute("game_id", game_id.to_string())
+            .add_event(shot_event(field, field_value));
+        Ok(response.add_events(next_turn(deps.storage, &mut game, game_id, &shooter, defender, field_value)?))
+    }
+
+    /// Passes the turn to the defender, or keeps it with the shooter after a
+    /// hit when the game grants extra turns. The turn timer was already reset
+    /// by the shot.
+    fn next_turn(
+        storage: &mut dyn Storage,
+        game: &mut Game,
+        game_id: u64,
+        shooter: &Player,
+        defender: Player,
+        hit: bool
+    ) -> StdResult<Option<Event>> {
+        let extra_turn = hit && game.config.extra_turn;
+        game.state.turn = if extra_turn { shooter.address.clone() } else { defender.address };
+        GAMES.save(storage, game_id, game)?;
+
+        Ok(extra_turn.then(|| {
+            Event::new("extra_turn")
+                .add_attribute("player", shooter.address.to_string())
+                .add_attribute("deadline", (game.state.last_turn_time + game.config.turn_duration).to_string())
+        }))
+    }
+
+    /// Logs a proven shot and marks a hit on the defender's board, which the
+    /// caller still has to save.
+    #[allow(clippy::too_many_arguments)]
+    fn record_shot(
+        storage: &mut dyn Storage,
+        time: u64,
+        game: &mut Game,
+        game_id: u64,
+        shooter: &Addr,
+        defender: &mut Player,
+        field: (usize, usize),
+        hit: bool
+    ) -> StdResult<()> {
+        SHOTS.save(storage, (game_id, shooter, game.config.index(field)), &Empty {})?;
+        MOVES.save(storage, (game_id, game.state.moves), &Move {
+            index: game.state.moves,
+            shooter: shooter.clone(),
+            field,
+            hit,
+            time,
+        })?;
+        game.state.moves += 1;
+
+        if hit {
+            defender.board.sank.push(field);
+        }
+        Ok(())
+    }
+
+    fn shot_event(field: (usize, usize), hit: bool) -> Event {
+        if hit {
+            Event::new("ship_sank").add_attribute("sank", format!("{:?}", field))
+        } else {
+            Event::new("ship_missed").add_attribute("missed", format!("{:?}", field))
+        }
+    }
+
+    /// Verifies that `value` and `salt` are committed at `field` in the board
+    /// with the given merkle root, see the board-commitment crate for the tree.
+    /// The proof has to be in the game's format.
+    pub fn verify_proof(
+        game_config: &GameConfig,
+        field: (usize, usize),
+        value: bool,
+        salt: &str,
+        proof: Proof,
+        merkle_root: &str
+    ) -> bool {
+        let nodes = match (&game_config.proof_format, proof) {
+            (ProofFormat::Hex, Proof::Hex(nodes)) => {
+                return board_commitment::verify(
+                    merkle_root,
+                    game_config.width,
+                    game_config.height,
+                    field,
+                    value,
+                    salt,
+                    &nodes
+                );
+            }
+            (ProofFormat::Binary, Proof::Binary(nodes)) if nodes.len().is_multiple_of(32) => nodes,
+            _ => return false,
+        };
+        let Some(root) = board_commitment::decode_node(merkle_root) else {
+            return false;
+        };
+        let nodes: Vec<Node> = nodes
+            .chunks_exact(32)
+            .map(|node| node.try_into().unwrap())
+            .collect();
+
+        board_commitment::verify_nodes(
+            Encoding::Binary,
+            &root,
+            game_config.width,
+            game_config.height,
+            field,
+            value,
+            salt,
+            &nodes
+        )
+    }
+
+    /// Recomputes the merkle root of a fully revealed board, or `None` if the
+    /// board or salts do not have the configured dimensions.
+    pub fn board_root(
+        game_config: &GameConfig,
+        board: &[Vec<bool>],
+        salts: &[Vec<String>]
+    ) -> Option<String> {
+        if board.len() != game_config.height || board.iter().any(|row| row.len() != game_config.width) {
+            return None;
+        }
+
+        BoardCommitment::with_encoding(board, salts, game_config.proof_format.encoding())
+            .map(|commitment| commitment.root())
+    }
+
+    /// Groups the occupied fields of `board` into orthogonally connected components.
+    pub fn ship_components(board: &[Vec<bool>]) -> Vec<Vec<(usize, usize)>> {
+        let height = board.len();
+        let width = board.first().map_or(0, |row| row.len());
+        let mut visited = vec![vec![false; width]; height];
+        let mut components = vec![];
+
+        for row in 0..height {
+            for column in 0..width {
+                if !board[row][column] || visited[row][column] {
+                    continue;
+                }
+
+                let mut cells = vec![];
+                let mut stack = vec![(row, column)];
+                visited[row][column] = true;
+                while let Some((r, c)) = stack.pop() {
+                    cells.push((r, c));
+                    for (nr, nc) in neighbours((r, c)) {
+                        if nr < height && nc < width && board[nr][nc] && !visited[nr][nc] {
+                            visited[nr][nc] = true;
+                            stack.push((nr, nc));
+                        }
+                    }
+                }
+                components.push(cells);
+            }
+        }
+
+        components
     }
 
-    if msg.players[0].stake != msg.players[1].stake {
-        return Err(ContractError::InvalidStake {})
+    /// Orthogonal neighbours of `field`, the ones off the board wrap around
+    /// to indexes that fail any bounds check.
+    pub fn neighbours((r, c): (usize, usize)) -> [(usize, usize); 4] {
+        [
+            (r.wrapping_sub(1), c),
+            (r + 1, c),
+            (r, c.wrapping_sub(1)),
+            (r, c + 1),
+        ]
+    }
+
+    /// Checks that the board holds exactly the configured fleet. Every ship is
+    /// a straight line of fields, and ships may not touch each other
+    /// horizontally or vertically.
+    pub fn fleet_is_legal(fleet: &[usize], board: &[Vec<bool>]) -> bool {
+        let mut ships = vec![];
+        for cells in ship_components(board) {
+            let (row, column) = cells[0];
+            let straight = cells.iter().all(|&(r, _)| r == row)
+                || cells.iter().all(|&(_, c)| c == column);
+            if !straight {
+                return false;
+            }
+            ships.push(cells.len());
+        }
+
+        let mut expected = fleet.to_vec();
+        expected.sort_unstable();
+        ships.sort_unstable();
+        ships == expected
+    }
+
+    /// Checks that every radar scan of the board was answered truthfully.
+    pub fn scans_answered(scans: &[Scan], board: &[Vec<bool>]) -> bool {
+        scans.iter().all(|scan| {
+            let found = scan.segment.fields().iter().any(|&(row, column)| board[row][column]);
+            found == scan.found
+        })
+    }
+
+    pub fn timeout_win(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        game_id: u64
+    ) -> Result<Response, ContractError> {
+        let mut game = GAMES.load(deps.storage, game_id)?;
+
+        if game.state.finished {
+            return Err(ContractError::GameFinished {});
+        }
+
+        let player = PLAYERS.load(deps.storage, (game_id, &info.sender))?;
+        let opponent_address = if game.state.started {
+            game.state.turn.clone()
+        } else if game.config.tournament.is_some() && !player.board.fields.is_empty() {
+            // tournament games are forfeited by not committing a board in time
+            load_opponent(deps.as_ref(), game_id, &info.sender)?.address
+        } else {
+            return Err(ContractError::GameNotStarted {});
+        };
+
+        if player.address == opponent_address {
+            return Err(ContractError::Unauthorized {  })
+        }
+
+        let now = env.block.time.seconds();
+        if now <= game.state.last_turn_time + game.config.turn_duration {
+            return Err(ContractError::TurnNotExpired {  });
+        }
+
+        finish(&mut game, &player.address, now);
+        game.state.timed_out = true;
+        GAMES.save(deps.storage, game_id, &game)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "timeout_check")
+            .add_attribute("game_id", game_id.to_string())
+            .add_attribute("winner", player.address.to_string())
+            .add_attribute("reveal_deadline", game.state.reveal_deadline.to_string())
+            .add_event(Event::new("game_won").add_attribute("sank", format!("{:?}", (-1, -1))))
+        )
+    }
+
+    /// Loads a running game together with the sending player and their opponent.
+    pub fn load_running_game(
+        deps: Deps,
+        game_id: u64,
+        sender: &Addr
+    ) -> Result<(Game, Player, Player), ContractError> {
+        let game = GAMES.load(deps.storage, game_id)?;
+
+        if !game.state.started {
+            return Err(ContractError::GameNotStarted {});
+        }
+
+        if game.state.finished {
+            return Err(ContractError::GameFinished {});
+        }
+
+        let player = PLAYERS
+            .may_load(deps.storage, (game_id, sender))?
+            .ok_or(ContractError::Unauthorized {})?;
+        let opponent = load_opponent(deps, game_id, sender)?;
+
+        Ok((game, player, opponent))
+    }
+
+    /// Concedes the game, the opponent wins and the boards are revealed as usual.
+    pub fn resign(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        game_id: u64
+    ) -> Result<Response, ContractError> {
+        let (mut game, player, opponent) = load_running_game(deps.as_ref(), game_id, &info.sender)?;
+
+        finish(&mut game, &opponent.address, env.block.time.seconds());
+        GAMES.save(deps.storage, game_id, &game)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "resign")
+            .add_attribute("game_id", game_id.to_string())
+            .add_attribute("winner", opponent.address.to_string())
+            .add_attribute("reveal_deadline", game.state.reveal_deadline.to_string())
+            .add_event(Event::new("resigned").add_attribute("player", player.address.to_string()))
+        )
     }
 
-    for player in msg.players {
-        let address = deps.api.addr_validate(&player.address)?;
-        let stake = player.stake;
+    pub fn offer_draw(
+        deps: DepsMut,
+        info: MessageInfo,
+        game_id: u64
+    ) -> Result<Response, ContractError> {
+        let (mut game, player, _) = load_running_game(deps.as_ref(), game_id, &info.sender)?;
 
-        let board = Board {
-            fields: player.board,
-            sank: vec![],
-        };
+        // a tournament match has to produce a winner
+        if game.config.tournament.is_some() {
+            return Err(ContractError::TournamentGame {});
+        }
 
-        let player = Player {
-            address: address.clone(),
-            stake: stake,
-            board: board,
-        };
+        game.state.draw_offer = Some(player.address.clone());
+        GAMES.save(deps.storage, game_id, &game)?;
 
-        PLAYERS.save(deps.storage, address, &player)?;
+        Ok(Response::new()
+            .add_attribute("action", "offer_draw")
+            .add_attribute("game_id", game_id.to_string())
+            .add_event(Event::new("draw_offered").add_attribute("player", player.address.to_string()))
+        )
     }
 
-    Ok(Response::new())
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    msg: ExecuteMsg
-) -> Result<Response, ContractError> {
-    match msg {
-        ExecuteMsg::StartGame {} => 
-            execute::start_game(deps, env, info),
-        ExecuteMsg::Play {field, value, proof} => 
-            execute::play(deps, env, info, field, value, proof),
-        ExecuteMsg::TimeoutWin {} => 
-            execute::timeout_win(deps, env, info)
-    }
-}
+    /// Ends the game in a draw offered by the opponent. Both stakes are
+    /// refunded minus the fee and the game is settled right away.
+    pub fn accept_draw(
+        deps: DepsMut,
+        info: MessageInfo,
+        game_id: u64
+    ) -> Result<Response, ContractError> {
+        let (mut game, player, opponent) = load_running_game(deps.as_ref(), game_id, &info.sender)?;
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(
-    deps: Deps,
-    _env: Env,
-    msg: QueryMsg
-) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetPlayers {} => to_json_binary(&query::get_players(deps)?),
-        QueryMsg::GetGameConfig {} => to_json_binary(&query::get_game_config(deps)?),
-        QueryMsg::GetGameState {} => to_json_binary(&query::get_game_state(deps)?),
-    }
-}
+        if game.state.draw_offer.as_ref() != Some(&opponent.address) {
+            return Err(ContractError::NoDrawOffer {});
+        }
 
-mod execute {
-    use cosmwasm_std::{Addr, Event, Order};
-    use cw20::Cw20ExecuteMsg;
-    use sha2::{Digest, Sha256};
-    use hex;
+        game.state.finished = true;
+        game.state.settled = true;
+        game.state.draw_offer = None;
+        GAMES.save(deps.storage, game_id, &game)?;
+
+        let asset = &game.config.asset;
+        let mut response = Response::new()
+            .add_attribute("action", "accept_draw")
+            .add_attribute("game_id", game_id.to_string());
+
+        let mut fee_retained = Uint128::zero();
+        for player in [&player, &opponent] {
+            let fee = player.stake.multiply_ratio(game.config.fee_percentage, 100u128);
+            let refund = player.stake.checked_sub(fee)
+                .map_err(|_| ContractError::Overflow {})?;
+            fee_retained += fee;
+            record_stats(deps.storage, game_id, player, Outcome::Draw, refund)?;
+
+            response = response
+                .add_message(transfer(player.address.clone(), refund, asset)?)
+                .add_event(
+                    Event::new("refund")
+                        .add_attribute("player", player.address.to_string())
+                        .add_attribute("amount", refund.to_string())
+                );
+        }
 
-    use crate::{msg::ProofStep, state::{FEE_PERCENTAGE, REWARD_PERCENTAGE, TURN_DURATION}};
+        if let Some(fee_msg) = route_fee(deps.storage, asset, fee_retained)? {
+            response = response.add_message(fee_msg);
+        }
+        let ratings = update_ratings(deps.storage, &player.address, &opponent.address, Decimal::percent(50))?;
 
-    use super::*;
+        Ok(response
+            .add_attribute("fee_retained", fee_retained.to_string())
+            .add_event(ratings)
+            .add_event(Event::new("draw"))
+        )
+    }
 
-    pub fn start_game(
-        deps:DepsMut,
-        env: Env,
-        info: MessageInfo
+    /// Calls off a game that has not started yet, refunding the deposits made so far.
+    pub fn cancel(
+        deps: DepsMut,
+        info: MessageInfo,
+        game_id: u64
     ) -> Result<Response, ContractError> {
-        let game_config = GAME_CONFIG.load(deps.storage)?;
-        let mut game_state = GAME_STATE.load(deps.storage)?;
+        let mut game = GAMES.load(deps.storage, game_id)?;
+
+        if game.config.tournament.is_some() {
+            return Err(ContractError::TournamentGame {});
+        }
 
-        if game_state.started {
+        if game.state.started {
             return Err(ContractError::GameStarted {});
         }
 
-        if game_state.finished {
+        if game.state.finished {
             return Err(ContractError::GameFinished {});
         }
 
-        let caller = info.sender.clone();
-        let players: Vec<Player> = PLAYERS
+        if !PLAYERS.has(deps.storage, (game_id, &info.sender)) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        game.state.finished = true;
+        game.state.settled = true;
+        GAMES.save(deps.storage, game_id, &game)?;
+        OPEN_GAMES.remove(deps.storage, game_id);
+
+        let players = PLAYERS
+            .prefix(game_id)
             .range(deps.storage, None, None, Order::Ascending)
-            .map(|res| res.map(|(_, player)| player))
-            .collect::<StdResult<Vec<Player>>>()?;
-        if players.iter().all(|player| player.address != caller) {
-            return Err(ContractError::Unauthorized{});
-        }
-
-        let token_addr = game_config.token_address;
-        let mut messages: Vec<cosmwasm_std::CosmosMsg> = vec![];
-
-        for player in &players {
-            let transfer_msg = Cw20ExecuteMsg::TransferFrom {
-                owner: player.address.to_string(),
-                recipient: env.contract.address.to_string(),
-                amount: player.stake
-            };
-            messages.push(
-                cosmwasm_std::WasmMsg::Execute {
-                    contract_addr: token_addr.to_string(),
-                    msg: to_json_binary(&transfer_msg)?, 
-                    funds: vec![]
-                }.into()
-            );
+            .map(|item| item.map(|(_, player)| player))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut response = Response::new()
+            .add_attribute("action", "cancel")
+            .add_attribute("game_id", game_id.to_string())
+            .add_event(Event::new("game_cancelled").add_attribute("player", info.sender.to_string()));
+
+        for mut player in players.into_iter().filter(|player| player.deposited) {
+            player.deposited = false;
+            PLAYERS.save(deps.storage, (game_id, &player.address), &player)?;
+
+            response = response
+                .add_message(transfer(player.address.clone(), player.stake, &game.config.asset)?)
+                .add_event(
+                    Event::new("refund")
+                        .add_attribute("player", player.address.to_string())
+                        .add_attribute("amount", player.stake.to_string())
+                );
         }
 
-        game_state.started = true;
-        game_state.last_turn_time = env.block.time.seconds();
-        GAME_STATE.save(deps.storage, &game_state)?;
+        Ok(response)
+    }
 
-        Ok(Response::new()
-            .add_attribute("action", "start_game")
-            .add_attribute("stake", players[0].stake.to_string())
-            .add_message(messages[0].clone())
-            .add_message(messages[1].clone())
-        )
+    /// Ends the game and opens the reveal window. Stakes stay in escrow until
+    /// the game is settled.
+    pub fn finish(game: &mut Game, winner: &Addr, now: u64) {
+        game.state.finished = true;
+        game.state.winner = Some(winner.clone());
+        game.state.reveal_deadline = now + REVEAL_DURATION;
     }
 
-    pub fn play(
+    pub fn reveal(
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        field: (usize, usize),
-        field_value: bool,
-        proof: Vec<ProofStep>
+        game_id: u64,
+        board: Vec<Vec<bool>>,
+        salts: Vec<Vec<String>>
     ) -> Result<Response, ContractError> {
-        let game_config = GAME_CONFIG.load(deps.storage)?;
-        let mut game_state = GAME_STATE.load(deps.storage)?;
+        let game = GAMES.load(deps.storage, game_id)?;
 
-        if !game_state.started {
-            return Err(ContractError::GameNotStarted {});
+        if !game.state.finished {
+            return Err(ContractError::GameNotFinished {});
         }
 
-        if game_state.finished {
-            return Err(ContractError::GameFinished {});
+        if game.state.settled || env.block.time.seconds() > game.state.reveal_deadline {
+            return Err(ContractError::RevealClosed {});
         }
 
-        if env.block.time.seconds() > game_state.last_turn_time + TURN_DURATION {
-            return Err(ContractError::TurnExpired {  });
+        let mut player = PLAYERS
+            .may_load(deps.storage, (game_id, &info.sender))?
+            .ok_or(ContractError::Unauthorized {})?;
+
+        if player.board.revealed.is_some() {
+            return Err(ContractError::AlreadyRevealed {});
         }
 
-        let player = info.sender;
-        if player != game_state.turn {
-            return Err(ContractError::WrongTurn {  })
+        if board_root(&game.config, &board, &salts).as_ref() != Some(&player.board.fields) {
+            return Err(ContractError::InvalidReveal {});
         }
-        let player = PLAYERS.load(deps.storage, player)?;
 
-        let opponent = PLAYERS
-            .range(deps.storage, None, None, Order::Ascending)
-            .find_map(|item| {
-                let (addr, player_data) = item.ok()?;
-                if addr != player.address {
-                    Some(player_data)
-                } else {
-                    None
-                }
-        })
-        .ok_or(ContractError::PlayerNotFound {  });
+        let legal = fleet_is_legal(&game.config.fleet, &board) && scans_answered(&player.scans, &board);
+        player.board.revealed = Some(board);
+        PLAYERS.save(deps.storage, (game_id, &info.sender), &player)?;
 
-        let opponent = opponent?;
+        Ok(Response::new()
+            .add_attribute("action", "reveal")
+            .add_attribute("game_id", game_id.to_string())
+            .add_attribute("player", info.sender.to_string())
+            .add_attribute("legal", legal.to_string())
+        )
+    }
 
-        if !verify_proof(field_value, proof, &opponent.board.fields) {
-            return Err(ContractError::InvalidProof {  });
+    /// Pays out a finished game once both boards are revealed or the reveal
+    /// window has closed. A board counts as legal if it holds the configured
+    /// fleet and agrees with every radar answer given about it. The pot goes to
+    /// the winner if their board is legal, or if they did not reveal and the
+    /// loser's board is not legal either, otherwise to a loser with a legal
+    /// board, and is retained by the contract if neither gets it. A player is
+    /// slashed unless their board was legal, except for a winner who did not
+    /// reveal against a loser without a legal board.
+    pub fn settle(
+        deps: DepsMut,
+        env: Env,
+        game_id: u64
+    ) -> Result<Response, ContractError> {
+        let mut game = GAMES.load(deps.storage, game_id)?;
+
+        // drawn and cancelled games are settled when they end
+        if game.state.settled {
+            return Err(ContractError::AlreadySettled {});
         }
 
-        let opponent_sunk = &opponent.board.sank;
-        if opponent_sunk.contains(&field) {
-            return Err(ContractError::AlreadySunk {});
+        let winner = match (&game.state.winner, game.state.finished) {
+            (Some(winner), true) => winner.clone(),
+            _ => return Err(ContractError::GameNotFinished {}),
+        };
+
+        let winner = PLAYERS.load(deps.storage, (game_id, &winner))?;
+        let loser = load_opponent(deps.as_ref(), game_id, &winner.address)?;
+
+        let all_revealed = winner.board.revealed.is_some() && loser.board.revealed.is_some();
+        if !all_revealed && env.block.time.seconds() <= game.state.reveal_deadline {
+            return Err(ContractError::RevealNotClosed {});
         }
 
-        game_state.last_turn_time = env.block.time.seconds();
-        if field_value {
-            let opponent = PLAYERS
-                .update::<_, ContractError>(
-                    deps.storage, 
-                    opponent.address.clone(), 
-                    |player| {
-                        let mut player = player.ok_or(ContractError::PlayerNotFound {})?;
-                        player.board.sank.push(field);
-                        Ok(player)
-                    }
-                )?;
+        let fleet = game.config.fleet.clone();
+        let is_legal = |player: &Player| player.board.revealed
+            .as_ref()
+            .is_some_and(|board| fleet_is_legal(&fleet, board) && scans_answered(&player.scans, board));
+        let winner_paid = is_legal(&winner) || (winner.board.revealed.is_none() && !is_legal(&loser));
+
+        game.state.settled = true;
+        game.state.paid_to = if winner_paid {
+            Some(winner.address.clone())
+        } else if is_legal(&loser) {
+            Some(loser.address.clone())
+        } else {
+            None
+        };
+        GAMES.save(deps.storage, game_id, &game)?;
+
+        let total_amount = winner.stake + loser.stake;
+        let fee = total_amount.multiply_ratio(game.config.fee_percentage, 100u128);
+        let payout = total_amount.checked_sub(fee)
+            .map_err(|_| ContractError::Overflow {})?;
+        let asset = game.config.asset.clone();
+
+        let mut response = Response::new()
+            .add_attribute("action", "settle")
+            .add_attribute("game_id", game_id.to_string());
+
+        for (player, kept) in [(&winner, winner_paid), (&loser, is_legal(&loser))] {
+            if !kept {
+                response = response.add_event(
+                    Event::new("slashed")
+                        .add_attribute("game_id", game_id.to_string())
+                        .add_attribute("player", player.address.to_string())
+                );
+            }
+        }
+
+        // the whole pot is retained when neither player gets it
+        let fee_retained = if winner_paid || is_legal(&loser) { fee } else { total_amount };
+        if let Some(fee_msg) = route_fee(deps.storage, &asset, fee_retained)? {
+            response = response.add_message(fee_msg);
+        }
+        response = response
+            .add_attribute("fee_retained", fee_retained.to_string());
+
+        let (winner_received, loser_received) = if winner_paid {
+            (payout + payout.multiply_ratio(game.config.reward_percentage, 100u128), Uint128::zero())
+        } else if is_legal(&loser) {
+            (Uint128::zero(), payout)
+        } else {
+            (Uint128::zero(), Uint128::zero())
+        };
+        record_stats(deps.storage, game_id, &winner, Outcome::Won, winner_received)?;
+        let loser_outcome = if game.state.timed_out { Outcome::TimedOut } else { Outcome::Lost };
+        record_stats(deps.storage, game_id, &loser, loser_outcome, loser_received)?;
+
+        // the rated winner is whoever gets the pot, nobody if neither gets it
+        if winner_paid || is_legal(&loser) {
+            let winner_score = if winner_paid { Decimal::one() } else { Decimal::zero() };
+            response = response.add_event(
+                update_ratings(deps.storage, &winner.address, &loser.address, winner_score)?
+            );
+        }
 
-            if opponent.board.sank.len() == game_config.ships {
-                game_state.finished = true;
+        // the player who gets the pot advances in the bracket
+        if let Some((tournament_id, index)) = game.config.tournament {
+            let advancing = game.state.paid_to.clone();
+            let (messages, events) = tournament::record_result(deps.storage, &env, tournament_id, index, advancing)?;
+            response = response.add_messages(messages).add_events(events);
+        }
 
-                let total_amount = player.stake + opponent.stake;
-                let fee = total_amount.multiply_ratio(FEE_PERCENTAGE, 100u128);
-                let payout = total_amount.checked_sub(fee)
-                    .map_err(|_| ContractError::Overflow {})?;
-                let token_address = game_config.token_address;
+        if let Some(fee_msg) = betting::take_house_fee(deps.storage, game_id, &game)? {
+            response = response.add_message(fee_msg);
+        }
 
-                //transfer funds to winner
-                let transfer_msg = transfer(
-                    player.address.clone(), 
-                    payout, 
-                    token_address.clone()
-                )?;
+        // tournament games are played for the prize pool, not for stakes
+        if payout.is_zero() {
+            return Ok(response
+                .add_attribute("payout", Uint128::zero().to_string())
+            );
+        }
 
-                // mint reword for winner
-                let reward = payout.multiply_ratio(REWARD_PERCENTAGE, 100u128);
+        if winner_paid {
+            //transfer funds to winner
+            let transfer_msg = transfer(
+                winner.address.clone(), 
+                payout, 
+                &asset
+            )?;
+            response = response
+                .add_attribute("recipient", winner.address.to_string())
+                .add_attribute("payout", payout.to_string())
+                .add_message(transfer_msg);
+
+            // mint reword for winner, native games are created without one
+            let reward = payout.multiply_ratio(game.config.reward_percentage, 100u128);
+            if let (StakeAsset::Cw20 { address }, false) = (&asset, reward.is_zero()) {
                 let mint_msg = mint(
-                    player.address.clone(), 
+                    winner.address.clone(), 
                     reward, 
-                    token_address
+                    address.clone()
                 )?;
-
-                GAME_STATE.save(deps.storage, &game_state)?;
-
-                return Ok(Response::new()
-                    .add_attribute("action", "play")
-                    .add_attribute("winner", player.address.to_string())
-                    .add_attribute("payout", payout.to_string())
-                    .add_attribute("fee_retained", fee.to_string())
-                    .add_event(Event::new("game_won").add_attribute("sank", format!("{:?}", field)))
-                    .add_message(transfer_msg)
-                    .add_attribute("minted_reward", reward.to_string())
-                    .add_message(mint_msg)
-                );
+                response = response.add_message(mint_msg);
             }
 
-            game_state.turn = opponent.address;
-            GAME_STATE.save(deps.storage, &game_state)?;
-            return Ok(
-                Response::new()
-                    .add_attribute("action", "play")
-                    .add_event(Event::new("ship_sank").add_attribute("sank", format!("{:?}", field)))
+            return Ok(response
+                .add_attribute("minted_reward", reward.to_string())
+            );
+        }
+
+        if is_legal(&loser) {
+            let transfer_msg = transfer(
+                loser.address.clone(), 
+                payout, 
+                &asset
+            )?;
+
+            return Ok(response
+                .add_attribute("recipient", loser.address.to_string())
+                .add_attribute("payout", payout.to_string())
+                .add_message(transfer_msg)
             );
         }
 
-        game_state.turn = opponent.address;
-        GAME_STATE.save(deps.storage, &game_state)?;
-        Ok(
-            Response::new()
-                .add_attribute("action", "play")
-                .add_event(Event::new("ship_missed").add_attribute("missed", format!("{:?}", field)))
+        Ok(response
+            .add_attribute("payout", Uint128::zero().to_string())
         )
+    }
 
+    pub enum Outcome {
+        Won,
+        Lost,
+        TimedOut,
+        Draw,
     }
 
-    pub fn verify_proof(value: bool, proof: Vec<ProofStep>, merkle_root: &str) -> bool {
-        let mut current_hash = hash(value.to_string());
-        println!("{}", value);
+    /// Adds a settled game to the player's statistics. `received` is everything
+    /// the settlement paid the player, the refunded stake included.
+    pub fn record_stats(
+        storage: &mut dyn Storage,
+        game_id: u64,
+        player: &Player,
+        outcome: Outcome,
+        received: Uint128
+    ) -> Result<(), ContractError> {
+        let moves = MOVES
+            .prefix(game_id)
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let shots: Vec<_> = moves
+            .iter()
+            .filter(|(_, game_move)| game_move.shooter == player.address)
+            .collect();
+        let hits = shots.iter().filter(|(_, game_move)| game_move.hit).count() as u32;
+
+        let to_signed = |amount: Uint128| i128::try_from(amount.u128())
+            .map(Int128::new)
+            .map_err(|_| ContractError::Overflow {});
+        let net_won = to_signed(received)?
+            .checked_sub(to_signed(player.stake)?)
+            .map_err(|_| ContractError::Overflow {})?;
 
-        for step in proof {
-            println!("{}", current_hash);
-            if step.is_left {
-                current_hash = hash(step.hash + &current_hash);
-                continue;
+        player_stats().update::<_, ContractError>(storage, &player.address, |stats| {
+            let mut stats = stats.unwrap_or_default();
+            stats.games_played += 1;
+            match outcome {
+                Outcome::Won => stats.wins += 1,
+                Outcome::Lost => stats.losses += 1,
+                Outcome::TimedOut => {
+                    stats.losses += 1;
+                    stats.timeouts += 1;
+                }
+                Outcome::Draw => stats.draws += 1,
             }
-            current_hash = hash(current_hash + &step.hash);
-        }
+            stats.shots_fired += shots.len() as u32;
+            stats.hits += hits;
+            stats.net_won = stats.net_won
+                .checked_add(net_won)
+                .map_err(|_| ContractError::Overflow {})?;
+            Ok(stats)
+        })?;
+        Ok(())
+    }
+
+    /// Updates the elo ratings of both players of a game in which `player`
+    /// scored `score` (one for a win, a half for a draw, zero for a loss).
+    pub fn update_ratings(
+        storage: &mut dyn Storage,
+        player: &Addr,
+        opponent: &Addr,
+        score: Decimal
+    ) -> Result<Event, ContractError> {
+        let player_rating = RATINGS.may_load(storage, player)?.unwrap_or_default();
+        let opponent_rating = RATINGS.may_load(storage, opponent)?.unwrap_or_default();
+
+        let expected = expected_score(player_rating.rating, opponent_rating.rating)?;
+        let player_rating = rate(player_rating, score, expected)?;
+        let opponent_rating = rate(opponent_rating, Decimal::one() - score, Decimal::one() - expected)?;
+
+        RATINGS.save(storage, player, &player_rating)?;
+        RATINGS.save(storage, opponent, &opponent_rating)?;
+
+        Ok(Event::new("ratings_updated")
+            .add_attribute(player.to_string(), player_rating.rating.to_string())
+            .add_attribute(opponent.to_string(), opponent_rating.rating.to_string())
+        )
+    }
 
-        return current_hash == merkle_root;
+    /// Expected score of a player against an opponent, 1 / (1 + 10^(diff / 400)).
+    /// Differences beyond 800 points count as 800.
+    pub fn expected_score(rating: u32, opponent_rating: u32) -> Result<Decimal, ContractError> {
+        // 10^(1/400)
+        let base = Decimal::raw(1_005_773_063_001_738_243u128);
+        let diff = rating.abs_diff(opponent_rating).min(800);
+        let power = base.checked_pow(diff).map_err(|_| ContractError::Overflow {})?;
+
+        let stronger = Decimal::one() - Decimal::one() / (Decimal::one() + power);
+        if rating >= opponent_rating {
+            Ok(stronger)
+        } else {
+            Ok(Decimal::one() - stronger)
+        }
     }
 
-    pub fn hash(item: String) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(item.as_bytes());
-        let result = hasher.finalize();
-        hex::encode(result)
+    pub fn rate(rating: Rating, score: Decimal, expected: Decimal) -> Result<Rating, ContractError> {
+        let k_factor = if rating.games < PROVISIONAL_GAMES { PROVISIONAL_K_FACTOR } else { K_FACTOR };
+        let k_factor = Decimal::from_ratio(k_factor, 1u64);
+        let round = |change: Decimal| (change + Decimal::percent(50)).to_uint_floor().u128() as u32;
+
+        let new_rating = if score >= expected {
+            rating.rating.saturating_add(round(k_factor * (score - expected)))
+        } else {
+            rating.rating.saturating_sub(round(k_factor * (expected - score)))
+        };
+
+        Ok(Rating { rating: new_rating, games: rating.games + 1 })
     }
 
-    pub fn timeout_win(
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_config(
         deps: DepsMut,
-        env: Env,
-        info: MessageInfo
+        info: MessageInfo,
+        admin: Option<String>,
+        min_stake: Option<Uint128>,
+        fee_percentage: Option<u64>,
+        reward_percentage: Option<u64>,
+        turn_duration: Option<u64>,
+        fee_route: Option<FeeRoute>,
+        treasury: Option<String>,
+        native_denom: Option<String>,
+        native_min_stake: Option<Uint128>,
+        betting_moves: Option<u32>
     ) -> Result<Response, ContractError> {
-        let game_config = GAME_CONFIG.load(deps.storage)?;
-        let mut game_state = GAME_STATE.load(deps.storage)?;
+        let mut config = CONFIG.load(deps.storage)?;
+        if info.sender != config.admin {
+            return Err(ContractError::Unauthorized {});
+        }
 
-        if !game_state.started {
-            return Err(ContractError::GameNotStarted {});
+        if let Some(admin) = admin {
+            config.admin = deps.api.addr_validate(&admin)?;
+        }
+        if let Some(min_stake) = min_stake {
+            config.min_stake = min_stake;
+        }
+        if let Some(fee_percentage) = fee_percentage {
+            config.fee_percentage = fee_percentage;
+        }
+        if let Some(reward_percentage) = reward_percentage {
+            config.reward_percentage = reward_percentage;
+        }
+        if let Some(turn_duration) = turn_duration {
+            config.turn_duration = turn_duration;
+        }
+        if let Some(fee_route) = fee_route {
+            config.fee_route = fee_route;
+        }
+        if let Some(treasury) = treasury {
+            config.treasury = Some(deps.api.addr_validate(&treasury)?);
         }
+        if let Some(native_denom) = native_denom {
+            config.native_denom = Some(native_denom);
+        }
+        if let Some(native_min_stake) = native_min_stake {
+            config.native_min_stake = native_min_stake;
+        }
+        if let Some(betting_moves) = betting_moves {
+            config.betting_moves = betting_moves;
+        }
+        validate_config(&config)?;
+        CONFIG.save(deps.storage, &config)?;
 
-        if game_state.finished {
-            return Err(ContractError::GameFinished {});
+        Ok(Response::new()
+            .add_attribute("action", "update_config")
+            .add_attribute("admin", config.admin.to_string())
+            .add_attribute("min_stake", config.min_stake.to_string())
+            .add_attribute("fee_percentage", config.fee_percentage.to_string())
+            .add_attribute("reward_percentage", config.reward_percentage.to_string())
+            .add_attribute("turn_duration", config.turn_duration.to_string())
+            .add_attribute("fee_route", format!("{:?}", config.fee_route))
+        )
+    }
+
+    /// The fee and the reward together may not exceed the pot, every game
+    /// needs a stake and enough time to make a move, and fees can only be
+    /// routed to a treasury that is set.
+    pub fn validate_config(config: &Config) -> Result<(), ContractError> {
+        if config.min_stake.is_zero()
+            || (config.native_denom.is_some() && config.native_min_stake.is_zero())
+            || config.fee_percentage + config.reward_percentage > 100
+            || config.turn_duration < MIN_TURN_DURATION
+            || (config.fee_route == FeeRoute::Treasury && config.treasury.is_none()) {
+            return Err(ContractError::InvalidConfig {});
         }
+        Ok(())
+    }
 
-        let player = PLAYERS.load(deps.storage, info.sender)?;
-        let opponent_address = game_state.turn.clone();
+    pub fn add_token(
+        deps: DepsMut,
+        info: MessageInfo,
+        token_address: String,
+        min_stake: Uint128,
+        mint_reward: bool
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        if info.sender != config.admin {
+            return Err(ContractError::Unauthorized {});
+        }
 
-        if player.address == opponent_address {
-            return Err(ContractError::Unauthorized {  })
+        let token_address = deps.api.addr_validate(&token_address)?;
+        if token_address == config.token_address || min_stake.is_zero() {
+            return Err(ContractError::InvalidConfig {});
         }
+        ACCEPTED_TOKENS.save(deps.storage, &token_address, &AcceptedToken { min_stake, mint_reward })?;
 
-        let now = env.block.time.seconds();
-        if now <= game_state.last_turn_time + TURN_DURATION {
-            return Err(ContractError::TurnNotExpired {  });
+        Ok(Response::new()
+            .add_attribute("action", "add_token")
+            .add_attribute("token_address", token_address.to_string())
+            .add_attribute("min_stake", min_stake.to_string())
+            .add_attribute("mint_reward", mint_reward.to_string())
+        )
+    }
+
+    pub fn remove_token(
+        deps: DepsMut,
+        info: MessageInfo,
+        token_address: String
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        if info.sender != config.admin {
+            return Err(ContractError::Unauthorized {});
         }
 
-        game_state.finished = true;
+        let token_address = deps.api.addr_validate(&token_address)?;
+        if !ACCEPTED_TOKENS.has(deps.storage, &token_address) {
+            return Err(ContractError::TokenNotAccepted {});
+        }
+        ACCEPTED_TOKENS.remove(deps.storage, &token_address);
 
-        let opponent = PLAYERS.load(deps.storage, opponent_address)?;
-        let total_amount = player.stake + opponent.stake;
-        let fee = total_amount.multiply_ratio(FEE_PERCENTAGE, 100u128);
-        let payout = total_amount.checked_sub(fee)
-            .map_err(|_| ContractError::Overflow {})?;
-        let token_address = game_config.token_address;
+        Ok(Response::new()
+            .add_attribute("action", "remove_token")
+            .add_attribute("token_address", token_address.to_string())
+        )
+    }
 
-        //transfer funds to winner
-        let transfer_msg = transfer(
-            player.address.clone(), 
-            payout, 
-            token_address.clone()
-        )?;
+    pub fn withdraw_fees(
+        deps: DepsMut,
+        info: MessageInfo,
+        token_address: Option<String>,
+        denom: Option<String>,
+        recipient: String,
+        amount: Uint128
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        if info.sender != config.admin {
+            return Err(ContractError::Unauthorized {});
+        }
 
-        // mint reword for winner
-        let reward = payout.multiply_ratio(REWARD_PERCENTAGE, 100u128);
-        let mint_msg = mint(
-            player.address.clone(), 
-            reward, 
-            token_address
-        )?;
+        let asset = match (token_address, denom) {
+            (Some(_), Some(_)) => return Err(ContractError::InvalidToken {}),
+            (None, Some(denom)) => StakeAsset::Native { denom },
+            (Some(token_address), None) => StakeAsset::Cw20 { address: deps.api.addr_validate(&token_address)? },
+            (None, None) => StakeAsset::Cw20 { address: config.token_address },
+        };
+        let recipient = deps.api.addr_validate(&recipient)?;
+
+        let withdraw = |fees: Option<Uint128>| fees.unwrap_or_default()
+            .checked_sub(amount)
+            .map_err(|_| ContractError::InsufficientFees {});
+        match &asset {
+            StakeAsset::Native { denom } => ACCRUED_NATIVE_FEES.update(deps.storage, denom, withdraw)?,
+            StakeAsset::Cw20 { address } => ACCRUED_FEES.update(deps.storage, address, withdraw)?,
+        };
 
-        GAME_STATE.save(deps.storage, &game_state)?;
+        let transfer_msg = transfer(recipient.clone(), amount, &asset)?;
 
-        return Ok(Response::new()
-            .add_attribute("action", "timeout_check")
-            .add_attribute("winner", player.address.to_string())
-            .add_attribute("payout", payout.to_string())
-            .add_attribute("fee_retained", fee.to_string())
-            .add_event(Event::new("game_won").add_attribute("sank", format!("{:?}", (-1, -1))))
+        Ok(Response::new()
+            .add_attribute("action", "withdraw_fees")
+            .add_attribute("asset", asset.to_string())
+            .add_attribute("recipient", recipient.to_string())
+            .add_attribute("amount", amount.to_string())
             .add_message(transfer_msg)
-            .add_attribute("minted_reward", reward.to_string())
-            .add_message(mint_msg)
-        );
+        )
+    }
+
+    /// Sends the fee retained from a settled game where the config routes it:
+    /// it is either recorded as accrued, transferred to the treasury or burned.
+    pub fn route_fee(
+        storage: &mut dyn Storage,
+        asset: &StakeAsset,
+        fee: Uint128
+    ) -> Result<Option<CosmosMsg>, ContractError> {
+        if fee.is_zero() {
+            return Ok(None);
+        }
+
+        let config = CONFIG.load(storage)?;
+        match (config.fee_route, config.treasury, asset) {
+            (FeeRoute::Treasury, Some(treasury), _) =>
+                Ok(Some(transfer(treasury, fee, asset)?)),
+            (FeeRoute::Burn, _, StakeAsset::Native { denom }) =>
+                Ok(Some(BankMsg::Burn { amount: coins(fee.u128(), denom) }.into())),
+            (FeeRoute::Burn, _, StakeAsset::Cw20 { address }) => Ok(Some(cosmwasm_std::WasmMsg::Execute {
+                contract_addr: address.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Burn { amount: fee })?,
+                funds: vec![],
+            }.into())),
+            _ => {
+                let accrue = |fees: Option<Uint128>| fees.unwrap_or_default()
+                    .checked_add(fee)
+                    .map_err(|_| ContractError::Overflow {});
+                match asset {
+                    StakeAsset::Native { denom } => ACCRUED_NATIVE_FEES.update(storage, denom, accrue)?,
+                    StakeAsset::Cw20 { address } => ACCRUED_FEES.update(storage, address, accrue)?,
+                };
+                Ok(None)
+            }
+        }
     }
 
     pub fn transfer(
         recipient_addr: Addr,
         amount: Uint128,
-        token_addr: Addr
-    ) -> Result<cosmwasm_std::WasmMsg, cosmwasm_std::StdError> {
-        let transfer_msg = Cw20ExecuteMsg::Transfer { 
-            recipient: recipient_addr.to_string(), 
-            amount: amount
-        };
-        Ok(cosmwasm_std::WasmMsg::Execute {
-            contract_addr: token_addr.to_string(),
-            msg: to_json_binary(&transfer_msg)?,
-            funds: vec![],
-        })
+        asset: &StakeAsset
+    ) -> Result<CosmosMsg, cosmwasm_std::StdError> {
+        match asset {
+            StakeAsset::Native { denom } => Ok(BankMsg::Send {
+                to_address: recipient_addr.to_string(),
+                amount: coins(amount.u128(), denom),
+            }.into()),
+            StakeAsset::Cw20 { address } => {
+                let transfer_msg = Cw20ExecuteMsg::Transfer { 
+                    recipient: recipient_addr.to_string(), 
+                    amount
+                };
+                Ok(cosmwasm_std::WasmMsg::Execute {
+                    contract_addr: address.to_string(),
+                    msg: to_json_binary(&transfer_msg)?,
+                    funds: vec![],
+                }.into())
+            }
+        }
     }
 
     pub fn mint(
@@ -393,7 +2209,7 @@ mod execute {
     ) -> Result<cosmwasm_std::WasmMsg, cosmwasm_std::StdError> {
         let mint_msg = Cw20ExecuteMsg::Mint {
             recipient: recipient_addr.to_string(),
-            amount: amount
+            amount
         };
         Ok(cosmwasm_std::WasmMsg::Execute {
             contract_addr: token_address.to_string(), 
@@ -406,27 +2222,363 @@ mod execute {
 
 mod query {
     use cosmwasm_std::Order;
+    use cw_storage_plus::Bound;
+
+    use cosmwasm_std::{Addr, Decimal};
+
+    use crate::msg::{
+        AcceptedTokenInfo, AcceptedTokensResponse, AccruedFee, AccruedFeesResponse, BoardViewResponse, Cell,
+        ConfigResponse, GameConfigResponse, GameStateResponse, LeaderboardResponse, LeaderboardSort, MoveInfo,
+        MovesResponse, OpenGame, OpenGamesResponse, PlayerInfo, PlayerStatsResponse, PlayersResponse, RatingResponse,
+        ScanResponse
+    };
+    use crate::state::{
+        player_stats, PlayerStats, StakeAsset, ACCEPTED_TOKENS, ACCRUED_FEES, ACCRUED_NATIVE_FEES, MOVES,
+        PROVISIONAL_GAMES, RATINGS, SHOTS
+    };
+
+    use super::execute::{neighbours, ship_components};
 
     use super::*;
 
-    pub fn get_players(deps: Deps) -> StdResult<Vec<Player>> {
-        PLAYERS
+    // settings for pagination
+    const MAX_LIMIT: u32 = 30;
+    const DEFAULT_LIMIT: u32 = 10;
+
+    pub fn get_players(deps: Deps, game_id: u64) -> StdResult<PlayersResponse> {
+        let players = PLAYERS
+            .prefix(game_id)
             .range(deps.storage, None, None, Order::Ascending)
             .map(|item| {
                 let (_addr, player) = item?;
-                Ok(player)
+                Ok(PlayerInfo {
+                    address: player.address,
+                    stake: player.stake,
+                    deposited: player.deposited,
+                    board_root: player.board.fields,
+                    sank: player.board.sank,
+                    revealed: player.board.revealed,
+                    weapons_used: player.weapons_used,
+                    scans: player.scans
+                        .into_iter()
+                        .map(|scan| ScanResponse { segment: scan.segment, found: scan.found })
+                        .collect(),
+                })
             })
-            .collect()
+            .collect::<StdResult<_>>()?;
+        Ok(PlayersResponse { players })
+    }
+
+    pub fn get_game_config(deps: Deps, game_id: u64) -> StdResult<GameConfigResponse> {
+        let config = GAMES.load(deps.storage, game_id)?.config;
+        Ok(GameConfigResponse {
+            fleet_size: config.fleet_size(),
+            asset: config.asset,
+            creator: config.creator,
+            invitee: config.invitee,
+            stake: config.stake,
+            fleet: config.fleet,
+            width: config.width,
+            height: config.height,
+            turn_duration: config.turn_duration,
+            fee_percentage: config.fee_percentage,
+            reward_percentage: config.reward_percentage,
+            min_rating: config.min_rating,
+            max_rating: config.max_rating,
+            proof_format: config.proof_format,
+            salvo: config.salvo,
+            extra_turn: config.extra_turn,
+            weapons: config.weapons,
+            betting_moves: config.betting_moves,
+        })
     }
 
-    pub fn get_game_config(deps: Deps) -> StdResult<GameConfig> {
-        let game_config = GAME_CONFIG.load(deps.storage)?;
-        Ok(game_config)
+    pub fn get_config(deps: Deps) -> StdResult<ConfigResponse> {
+        let config = CONFIG.load(deps.storage)?;
+        Ok(ConfigResponse {
+            admin: config.admin,
+            token_address: config.token_address,
+            min_stake: config.min_stake,
+            fee_percentage: config.fee_percentage,
+            reward_percentage: config.reward_percentage,
+            turn_duration: config.turn_duration,
+            fee_route: config.fee_route,
+            treasury: config.treasury,
+            native_denom: config.native_denom,
+            native_min_stake: config.native_min_stake,
+            betting_moves: config.betting_moves,
+        })
     }
 
-    pub fn get_game_state(deps: Deps) -> StdResult<GameState> {
-        let game_state = GAME_STATE.load(deps.storage)?;
-        Ok(game_state)
+    pub fn get_accrued_fees(deps: Deps) -> StdResult<AccruedFeesResponse> {
+        let mut fees = ACCRUED_FEES
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (address, amount) = item?;
+                Ok(AccruedFee { asset: StakeAsset::Cw20 { address }, amount })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        for item in ACCRUED_NATIVE_FEES.range(deps.storage, None, None, Order::Ascending) {
+            let (denom, amount) = item?;
+            fees.push(AccruedFee { asset: StakeAsset::Native { denom }, amount });
+        }
+        Ok(AccruedFeesResponse { fees })
     }
 
-}
+    pub fn get_game_state(deps: Deps, game_id: u64) -> StdResult<GameStateResponse> {
+        let state = GAMES.load(deps.storage, game_id)?.state;
+        Ok(GameStateResponse {
+            started: state.started,
+            finished: state.finished,
+            settled: state.settled,
+            turn: state.turn,
+            last_turn_time: state.last_turn_time,
+            pending_shot: state.pending_shot,
+            pending_scan: state.pending_scan,
+            draw_offer: state.draw_offer,
+            moves: state.moves,
+            winner: state.winner,
+            reveal_deadline: state.reveal_deadline,
+            timed_out: state.timed_out,
+            paid_to: state.paid_to,
+        })
+    }
+
+    pub fn get_open_games(
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>
+    ) -> StdResult<OpenGamesResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let games = OPEN_GAMES
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|game_id| {
+                let game_id = game_id?;
+                let config = GAMES.load(deps.storage, game_id)?.config;
+                Ok(OpenGame {
+                    game_id,
+                    creator: config.creator,
+                    invitee: config.invitee,
+                    asset: config.asset,
+                    stake: config.stake,
+                    fleet: config.fleet,
+                    width: config.width,
+                    height: config.height,
+                    turn_duration: config.turn_duration,
+                    min_rating: config.min_rating,
+                    max_rating: config.max_rating,
+                    proof_format: config.proof_format,
+                    salvo: config.salvo,
+                    extra_turn: config.extra_turn,
+                    weapons: config.weapons,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(OpenGamesResponse { games })
+    }
+
+    pub fn get_accepted_tokens(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>
+    ) -> StdResult<AcceptedTokensResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        let tokens = ACCEPTED_TOKENS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (token_address, token) = item?;
+                Ok(AcceptedTokenInfo {
+                    token_address,
+                    min_stake: token.min_stake,
+                    mint_reward: token.mint_reward,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(AcceptedTokensResponse { tokens })
+    }
+
+    pub fn get_moves(
+        deps: Deps,
+        game_id: u64,
+        start_after: Option<u32>,
+        limit: Option<u32>
+    ) -> StdResult<MovesResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let moves = MOVES
+            .prefix(game_id)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (_index, game_move) = item?;
+                Ok(MoveInfo {
+                    index: game_move.index,
+                    shooter: game_move.shooter,
+                    field: game_move.field,
+                    hit: game_move.hit,
+                    time: game_move.time,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(MovesResponse { moves })
+    }
+
+    /// The opponent's board as `player` knows it from the shots resolved so far.
+    /// Ships count as sunk once all their fields are hit, which before the board
+    /// is revealed is only certain when the hits are surrounded by misses.
+    pub fn get_board_view(deps: Deps, game_id: u64, player: String) -> StdResult<BoardViewResponse> {
+        let player = deps.api.addr_validate(&player)?;
+        let config = GAMES.load(deps.storage, game_id)?.config;
+        if !PLAYERS.has(deps.storage, (game_id, &player)) {
+            return Err(StdError::not_found("player"));
+        }
+        let opponent = PLAYERS
+            .prefix(game_id)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, player)| player))
+            .find(|item| item.as_ref().map_or(true, |opponent| opponent.address != player))
+            .ok_or_else(|| StdError::not_found("opponent"))??;
+
+        board_view(deps, &config, game_id, &player, &opponent)
+    }
+
+    /// The board of `opponent` as `player` knows it, also used to count the
+    /// ships of a salvo game's shooter.
+    pub fn board_view(
+        deps: Deps,
+        config: &GameConfig,
+        game_id: u64,
+        player: &Addr,
+        opponent: &Player
+    ) -> StdResult<BoardViewResponse> {
+        let mut cells = vec![vec![Cell::Unknown; config.width]; config.height];
+        for index in SHOTS.prefix((game_id, player)).keys(deps.storage, None, None, Order::Ascending) {
+            let index = index? as usize;
+            cells[index / config.width][index % config.width] = Cell::Miss;
+        }
+        for &(row, column) in &opponent.board.sank {
+            cells[row][column] = Cell::Hit;
+        }
+
+        let is_hit = |(row, column): (usize, usize)| cells[row][column] == Cell::Hit;
+        let sunk: Vec<_> = match &opponent.board.revealed {
+            Some(board) => ship_components(board)
+                .into_iter()
+                .filter(|ship| ship.iter().all(|&field| is_hit(field)))
+                .collect(),
+            None => {
+                let hits: Vec<Vec<bool>> = (0..config.height)
+                    .map(|row| (0..config.width).map(|column| is_hit((row, column))).collect())
+                    .collect();
+                ship_components(&hits)
+                    .into_iter()
+                    .filter(|ship| ship.iter().all(|&field| {
+                        neighbours(field).into_iter().all(|(r, c)| {
+                            !config.contains((r, c)) || cells[r][c] != Cell::Unknown
+                        })
+                    }))
+                    .collect()
+            }
+        };
+
+        for &(row, column) in sunk.iter().flatten() {
+            cells[row][column] = Cell::Sunk;
+        }
+
+        Ok(BoardViewResponse {
+            width: config.width,
+            height: config.height,
+            cells,
+            remaining_ships: config.fleet.len().saturating_sub(sunk.len()),
+        })
+    }
+
+    fn stats_response(address: Addr, stats: PlayerStats) -> PlayerStatsResponse {
+        let accuracy = if stats.shots_fired == 0 {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(stats.hits, stats.shots_fired)
+        };
+        PlayerStatsResponse {
+            address,
+            games_played: stats.games_played,
+            wins: stats.wins,
+            losses: stats.losses,
+            draws: stats.draws,
+            timeouts: stats.timeouts,
+            shots_fired: stats.shots_fired,
+            hits: stats.hits,
+            accuracy,
+            net_won: stats.net_won,
+        }
+    }
+
+    pub fn get_rating(deps: Deps, address: String) -> StdResult<RatingResponse> {
+        let address = deps.api.addr_validate(&address)?;
+        let rating = RATINGS.may_load(deps.storage, &address)?.unwrap_or_default();
+        Ok(RatingResponse {
+            address,
+            rating: rating.rating,
+            games: rating.games,
+            provisional: rating.games < PROVISIONAL_GAMES,
+        })
+    }
+
+    pub fn get_player_stats(deps: Deps, address: String) -> StdResult<PlayerStatsResponse> {
+        let address = deps.api.addr_validate(&address)?;
+        let stats = player_stats().may_load(deps.storage, &address)?.unwrap_or_default();
+        Ok(stats_response(address, stats))
+    }
+
+    pub fn leaderboard(
+        deps: Deps,
+        sort_by: LeaderboardSort,
+        start_after: Option<String>,
+        limit: Option<u32>
+    ) -> StdResult<LeaderboardResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = match start_after {
+            Some(address) => {
+                let address = deps.api.addr_validate(&address)?;
+                let stats = player_stats().load(deps.storage, &address)?;
+                Some((address, stats))
+            }
+            None => None,
+        };
+
+        // best players come first, so the page continues below the last entry
+        let stats = player_stats();
+        let players = match sort_by {
+            LeaderboardSort::Wins => stats.idx.wins.range(
+                deps.storage,
+                None,
+                start_after.as_ref().map(|(address, stats)| Bound::exclusive((stats.wins, address))),
+                Order::Descending
+            ),
+            LeaderboardSort::Earnings => stats.idx.earnings.range(
+                deps.storage,
+                None,
+                start_after.as_ref().map(|(address, stats)| Bound::exclusive((stats.net_won.i128(), address))),
+                Order::Descending
+            ),
+        }
+            .take(limit)
+            .map(|item| {
+                let (address, stats) = item?;
+                Ok(stats_response(address, stats))
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(LeaderboardResponse { players })
+    }
+
+}
\ No newline at end of file
@@ -1,4 +1,5 @@
 use cosmwasm_std::StdError;
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,21 +7,36 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
-    #[error("Invalid number of ships.")]
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("Invalid fleet.")]
     InvalidShips {},
 
     #[error("Invalid board.")]
     InvalidBoard {},
 
+    #[error("Invalid board root.")]
+    InvalidRoot {},
+
+    #[error("Invalid players.")]
+    InvalidPlayers {},
+
     #[error("Wrong player to play.")]
     WrongTurn {},
 
     #[error("Player not found.")]
     PlayerNotFound {},
 
+    #[error("Field is not on the board.")]
+    InvalidField {},
+
     #[error("Already sunk.")]
     AlreadySunk {},
 
+    #[error("Field was already fired at.")]
+    AlreadyFired {},
+
     #[error("Game is over.")]
     GameFinished {},
 
@@ -48,6 +64,111 @@ pub enum ContractError {
     #[error("Invalid proof.")]
     InvalidProof {},
 
+    #[error("Invalid salt.")]
+    InvalidSalt {},
+
+    #[error("Invalid turn duration.")]
+    InvalidTurnDuration {},
+
+    #[error("Invalid config.")]
+    InvalidConfig {},
+
+    #[error("Not enough accrued fees.")]
+    InsufficientFees {},
+
+    #[error("Rating is outside the range allowed by the game.")]
+    RatingOutOfRange {},
+
+    #[error("Game is not open.")]
+    GameNotOpen {},
+
+    #[error("Invalid token.")]
+    InvalidToken {},
+
+    #[error("Token is not accepted.")]
+    TokenNotAccepted {},
+
+    #[error("Stake already deposited.")]
+    AlreadyDeposited {},
+
+    #[error("No deposit to withdraw.")]
+    NoDeposit {},
+
+    #[error("Waiting for the answer to the last shot.")]
+    ShotPending {},
+
+    #[error("No shot to answer.")]
+    NoPendingShot {},
+
+    #[error("No radar scan to answer.")]
+    NoPendingScan {},
+
+    #[error("No draw offered by the opponent.")]
+    NoDrawOffer {},
+
+    #[error("Game is not over.")]
+    GameNotFinished {},
+
+    #[error("Revealed board does not match the commitment.")]
+    InvalidReveal {},
+
+    #[error("Board already revealed.")]
+    AlreadyRevealed {},
+
+    #[error("Reveal window closed.")]
+    RevealClosed {},
+
+    #[error("Reveal window still open.")]
+    RevealNotClosed {},
+
+    #[error("Game already settled.")]
+    AlreadySettled {},
+
+    #[error("Invalid tournament.")]
+    InvalidTournament {},
+
+    #[error("Tournament registration is closed.")]
+    RegistrationClosed {},
+
+    #[error("Already registered.")]
+    AlreadyRegistered {},
+
+    #[error("Tournament is full.")]
+    TournamentFull {},
+
+    #[error("Board already committed.")]
+    AlreadyCommitted {},
+
+    #[error("Not allowed in tournament games.")]
+    TournamentGame {},
+
+    #[error("Betting on this game is closed.")]
+    BettingClosed {},
+
+    #[error("Players cannot bet against themselves.")]
+    BetAgainstSelf {},
+
+    #[error("Already betting on the other player.")]
+    BetOnOpponent {},
+
+    #[error("No bet to claim.")]
+    NoBet {},
+
+    #[error("Invalid salvo.")]
+    InvalidSalvo {},
+
+    #[error("Salvo games are played with PlaySalvo.")]
+    SalvoGame {},
+
+    #[error("Not a salvo game.")]
+    NotSalvoGame {},
+
+    #[error("No uses of the weapon left.")]
+    NoWeaponUses {},
+
+    #[error("Shots do not cover the weapon's target.")]
+    InvalidTarget {},
+
 }
 
 // impl From<OverflowError> for ContractError {
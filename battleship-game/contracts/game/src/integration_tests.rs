@@ -2,39 +2,273 @@
 pub mod tests {
     use std::str::FromStr;
 
-    use cosmwasm_std::{Addr, Uint128};
+    use cosmwasm_std::{coins, Addr, Decimal, Event, Int128, Uint128};
     use cw20::{Cw20QueryMsg, BalanceResponse};
-    use cw_multi_test::{App, ContractWrapper, Executor, IntoAddr};
+    use cosmwasm_std::{to_json_binary, to_json_vec};
+    use cw_multi_test::{error::AnyResult, App, AppResponse, BankSudo, ContractWrapper, Executor, IntoAddr, SudoMsg};
     use cw20_base::contract::{instantiate as cw20_instantiate, execute as cw20_execute, query as cw20_query};
     use cw20_base::msg::{InstantiateMsg as Cw20InstantiateMsg, ExecuteMsg as Cw20ExecuteMsg};
-    use crate::msg::ProofStep;
-    use crate::state::{GameConfig, GameState};
+    use board_commitment::{hash, BoardCommitment, Encoding};
+    use crate::state::{FeeRoute, ProofFormat, Salvo, Segment, StakeAsset, Weapons};
     use crate::{
-        contract::{execute, instantiate, query}, 
+        contract::{execute, instantiate, migrate, query}, 
         msg::{
-            ExecuteMsg, InstantiateMsg, PlayerInstantiate, QueryMsg
-        }, state::Player, ContractError
+            AcceptedTokenInfo, AcceptedTokensResponse, AccruedFee, AccruedFeesResponse, BetResponse, BetTotal, BetsResponse,
+            BoardViewResponse, Cell, ConfigResponse, ExecuteMsg, GameConfigResponse, GameStateResponse,
+            InstantiateMsg, LeaderboardResponse, LeaderboardSort, MigrateMsg, MovesResponse, OpenGamesResponse,
+            PlayerStatsResponse, PlayersResponse, Proof, QueryMsg, RatingResponse, ReceiveMsg, Shot,
+            TournamentResponse, TournamentStatusResponse
+        }, ContractError
     };
 
-    pub fn mock_instantiate_msg(ships: usize, token_address: Addr) -> InstantiateMsg {
+    /// Off-chain view of a committed board, mirroring what the proof generator
+    /// keeps for every player.
+    pub struct MockBoard {
+        pub fields: Vec<Vec<bool>>,
+        pub salts: Vec<Vec<String>>,
+        commitment: BoardCommitment,
+    }
+
+    impl MockBoard {
+        pub fn new(fields: Vec<Vec<bool>>, seed: &str) -> Self {
+            Self::with_format(fields, seed, ProofFormat::Hex)
+        }
+
+        pub fn with_format(fields: Vec<Vec<bool>>, seed: &str, format: ProofFormat) -> Self {
+            let salts: Vec<Vec<String>> = fields
+                .iter()
+                .enumerate()
+                .map(|(row, values)| {
+                    (0..values.len())
+                        .map(|column| hash(&format!("{}:{}:{}", seed, row, column)))
+                        .collect()
+                })
+                .collect();
+            let commitment = BoardCommitment::with_encoding(&fields, &salts, format.encoding()).unwrap();
+
+            MockBoard { fields, salts, commitment }
+        }
+
+        pub fn root(&self) -> String {
+            self.commitment.root()
+        }
+
+        pub fn salt(&self, field: (usize, usize)) -> String {
+            self.salts[field.0][field.1].clone()
+        }
+
+        pub fn proof(&self, field: (usize, usize)) -> Proof {
+            match self.commitment.encoding() {
+                Encoding::Hex => Proof::Hex(self.commitment.proof(field).unwrap()),
+                Encoding::Binary => Proof::Binary(self.commitment.proof_nodes(field).unwrap().concat().into()),
+            }
+        }
+
+        pub fn shot(&self, field: (usize, usize)) -> Shot {
+            Shot {
+                field,
+                value: self.fields[field.0][field.1],
+                salt: self.salt(field),
+                proof: self.proof(field),
+            }
+        }
+
+        pub fn salvo(&self, game_id: u64, fields: &[(usize, usize)]) -> ExecuteMsg {
+            ExecuteMsg::PlaySalvo {
+                game_id,
+                shots: fields.iter().map(|&field| self.shot(field)).collect(),
+            }
+        }
+
+        pub fn reveal(&self, game_id: u64) -> ExecuteMsg {
+            ExecuteMsg::Reveal {
+                game_id,
+                board: self.fields.clone(),
+                salts: self.salts.clone(),
+            }
+        }
+
+        pub fn play(&self, game_id: u64, field: (usize, usize)) -> ExecuteMsg {
+            ExecuteMsg::Play {
+                game_id,
+                field,
+                value: self.fields[field.0][field.1],
+                salt: self.salt(field),
+                proof: self.proof(field),
+            }
+        }
+
+        pub fn answer(&self, game_id: u64, field: (usize, usize)) -> ExecuteMsg {
+            ExecuteMsg::Answer {
+                game_id,
+                value: self.fields[field.0][field.1],
+                salt: self.salt(field),
+                proof: self.proof(field),
+            }
+        }
+    }
+
+    pub fn player1_board() -> MockBoard {
+        MockBoard::new(
+            vec![
+                vec![false, false, false],
+                vec![false, true, false],
+                vec![false, false, false],
+            ],
+            "player1",
+        )
+    }
+
+    pub fn player2_board() -> MockBoard {
+        MockBoard::new(
+            vec![
+                vec![false, false, true],
+                vec![false, false, false],
+                vec![false, false, false],
+            ],
+            "player2",
+        )
+    }
+
+    pub fn mock_instantiate_msg(token_address: Addr) -> InstantiateMsg {
         InstantiateMsg {
-            ships: ships,
             token_address: token_address.to_string(),
-            players: vec![
-                PlayerInstantiate {
-                    address: "player1".into_addr().to_string(),
-                    stake: Uint128::new(1000),
-                    board: "372ecd2044c797715c6d02c9f5b0fd2594172620a632e8a7dd3f10bfa8f2df56".to_owned(),
-                },
-                PlayerInstantiate {
-                    address: "player2".into_addr().to_string(),
-                    stake: Uint128::new(1000),
-                    board: "ee5fd4795e374a9e78f447867419b5ea98e662383c0ee88622e00cc7f710165c".to_owned(),
-                },
-            ],
+            admin: None,
+            min_stake: None,
+            fee_percentage: None,
+            reward_percentage: None,
+            turn_duration: None,
+            fee_route: None,
+            treasury: None,
+            native_denom: None,
+            native_min_stake: None,
+            betting_moves: None,
+        }
+    }
+
+    pub fn mock_create_game_msg(fleet: Vec<usize>) -> ExecuteMsg {
+        ExecuteMsg::CreateGame {
+            stake: Uint128::new(1000),
+            fleet,
+            width: 3,
+            height: 3,
+            turn_duration: None,
+            board: player1_board().root(),
+            invitee: None,
+            min_rating: None,
+            max_rating: None,
+            token_address: None,
+            denom: None,
+            proof_format: None,
+            salvo: None,
+            extra_turn: None,
+            weapons: None,
+        }
+    }
+
+    pub fn mock_join_game_msg(game_id: u64) -> ExecuteMsg {
+        ExecuteMsg::JoinGame {
+            game_id,
+            board: player2_board().root(),
+        }
+    }
+
+    /// Opens a game as player1 and joins it as player2, returning its id.
+    pub fn create_and_join_game(app: &mut App, game_addr: &Addr, fleet: Vec<usize>) -> u64 {
+        let response = app
+            .execute_contract(
+                "player1".into_addr(),
+                game_addr.clone(),
+                &mock_create_game_msg(fleet),
+                &[],
+            )
+            .unwrap();
+
+        let game_id = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap()
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "game_id")
+            .unwrap()
+            .value
+            .parse()
+            .unwrap();
+
+        app.execute_contract(
+            "player2".into_addr(),
+            game_addr.clone(),
+            &mock_join_game_msg(game_id),
+            &[],
+        ).unwrap();
+
+        game_id
+    }
+
+    /// Sends `amount` tokens to the game contract with the given hook message.
+    pub fn send_stake(
+        app: &mut App,
+        cw20_addr: &Addr,
+        game_addr: &Addr,
+        sender: &Addr,
+        amount: u128,
+        msg: &ReceiveMsg
+    ) -> AnyResult<AppResponse> {
+        app.execute_contract(
+            sender.clone(),
+            cw20_addr.clone(),
+            &Cw20ExecuteMsg::Send {
+                contract: game_addr.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_json_binary(msg).unwrap(),
+            },
+            &[]
+        )
+    }
+
+    /// Deposits both players' stakes, which starts the game.
+    pub fn deposit_stakes(app: &mut App, cw20_addr: &Addr, game_addr: &Addr, game_id: u64) {
+        for player in ["player1", "player2"] {
+            send_stake(
+                app,
+                cw20_addr,
+                game_addr,
+                &player.into_addr(),
+                1000,
+                &ReceiveMsg::Deposit { game_id }
+            ).unwrap();
         }
     }
 
+    /// Deposits both stakes, lets player1's turn run out and settles the game
+    /// won by player2 on timeout, after only player2 revealed a legal board.
+    pub fn settle_by_timeout(app: &mut App, cw20_addr: &Addr, game_addr: &Addr, game_id: u64) -> AppResponse {
+        deposit_stakes(app, cw20_addr, game_addr, game_id);
+        app.update_block(|b| b.time = b.time.plus_seconds(1000));
+
+        app.execute_contract(
+            "player2".into_addr(),
+            game_addr.clone(),
+            &ExecuteMsg::TimeoutWin { game_id },
+            &[]
+        ).unwrap();
+        app.execute_contract(
+            "player2".into_addr(),
+            game_addr.clone(),
+            &player2_board().reveal(game_id),
+            &[]
+        ).unwrap();
+        app.update_block(|b| b.time = b.time.plus_seconds(301));
+
+        app.execute_contract(
+            "player2".into_addr(),
+            game_addr.clone(),
+            &ExecuteMsg::Settle { game_id },
+            &[]
+        ).unwrap()
+    }
+
     pub fn mock_cw20_instantiate_msg(
         player1_addr: Addr, 
         player2_addr: Addr,
@@ -91,12 +325,14 @@ pub mod tests {
             .instantiate_contract(
                 game_code_id, 
                 "owner".into_addr(),
-                &mock_instantiate_msg(1, cw20_addr.clone()), 
+                &mock_instantiate_msg(cw20_addr.clone()), 
                 &[], 
                 "Contract", 
                 None
         ).unwrap();
 
+        create_and_join_game(&mut app, &game_addr, vec![1]);
+
         app.execute_contract(
             "owner".into_addr(),
             cw20_addr.clone(),
@@ -106,28 +342,6 @@ pub mod tests {
             &[],
         ).unwrap();
 
-        app.execute_contract(
-            player1_addr,
-            cw20_addr.clone(),
-            &Cw20ExecuteMsg::IncreaseAllowance { 
-                spender: game_addr.to_string(),
-                amount: Uint128::new(100_000), 
-                expires: None 
-            },
-            &[]
-        ).unwrap();
-
-        app.execute_contract(
-            player2_addr,
-            cw20_addr.clone(),
-            &Cw20ExecuteMsg::IncreaseAllowance { 
-                spender: game_addr.to_string(),
-                amount: Uint128::new(100_000), 
-                expires: None 
-            },
-            &[]
-        ).unwrap();
-
         (cw20_addr, game_addr, app)
     }
 
@@ -137,232 +351,2240 @@ pub mod tests {
         let player2_addr = "player2".into_addr();
         let (cw20_address, address, app) = init_app(player1_addr, player2_addr);
 
-        let response: GameConfig = app
+        let response: GameConfigResponse = app
             .wrap()
-            .query_wasm_smart(address.clone(), &QueryMsg::GetGameConfig {})
+            .query_wasm_smart(address.clone(), &QueryMsg::GetGameConfig { game_id: 1 })
             .unwrap();
-        assert_eq!(response.ships, 1);
-        assert_eq!(response.token_address, cw20_address);
+        assert_eq!(response.fleet, vec![1]);
+        assert_eq!(response.width, 3);
+        assert_eq!(response.height, 3);
+        assert_eq!(response.asset, StakeAsset::Cw20 { address: cw20_address.clone() });
 
-        let response: GameState = app
+        let response: GameStateResponse = app
             .wrap()
-            .query_wasm_smart(address.clone(), &QueryMsg::GetGameState {})
+            .query_wasm_smart(address.clone(), &QueryMsg::GetGameState { game_id: 1 })
             .unwrap();
         assert_eq!(response.turn, "player1".into_addr());
-        assert_eq!(response.started, false);
-        assert_eq!(response.finished, false);
+        assert!(!response.started);
+        assert!(!response.finished);
 
-        let response: Vec<Player> = app
+        let response: PlayersResponse = app
             .wrap()
-            .query_wasm_smart(address.clone(), &QueryMsg::GetPlayers {  })
+            .query_wasm_smart(address.clone(), &QueryMsg::GetPlayers { game_id: 1 })
             .unwrap();
 
-        assert_eq!(response[1].address, "player1".into_addr());
-        assert_eq!(response[1].stake, Uint128::new(1000));
-        assert_eq!(response[1].board.fields, "372ecd2044c797715c6d02c9f5b0fd2594172620a632e8a7dd3f10bfa8f2df56".to_owned(),);
-        assert_eq!(response[1].board.sank, vec![]);
+        assert_eq!(response.players[1].address, "player1".into_addr());
+        assert_eq!(response.players[1].stake, Uint128::new(1000));
+        assert_eq!(response.players[1].board_root, player1_board().root());
+        assert_eq!(response.players[1].sank, vec![]);
 
-        assert_eq!(response[0].address, "player2".into_addr());
-        assert_eq!(response[0].stake, Uint128::new(1000));
-        assert_eq!(response[0].board.fields, "ee5fd4795e374a9e78f447867419b5ea98e662383c0ee88622e00cc7f710165c".to_owned());
-        assert_eq!(response[0].board.sank, vec![]);
+        assert_eq!(response.players[0].address, "player2".into_addr());
+        assert_eq!(response.players[0].stake, Uint128::new(1000));
+        assert_eq!(response.players[0].board_root, player2_board().root());
+        assert_eq!(response.players[0].sank, vec![]);
     }
 
     #[test]
     fn should_throw_invalid_ships_error() {
-        let mut app = App::default();
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (_, game_addr, mut app) = init_app(player1_addr, player2_addr);
+
+        // empty fleet, zero length ship, ship longer than the board, fleet larger than the board,
+        // fleet filling the board with touching ships
+        for fleet in [vec![], vec![0], vec![4], vec![3, 3, 3, 1], vec![3, 3, 3]] {
+            let err = app
+                .execute_contract(
+                    "owner".into_addr(),
+                    game_addr.clone(),
+                    &mock_create_game_msg(fleet),
+                    &[]
+                ).unwrap_err();
+
+            assert_eq!(ContractError::InvalidShips {  }, err.downcast().unwrap())
+        }
 
-        let code = ContractWrapper::new(execute, instantiate, query);
-        let code_id = app.store_code(Box::new(code));
+        // two ships of two always touch on a 2x2 board
+        let mut msg = mock_create_game_msg(vec![2, 2]);
+        if let ExecuteMsg::CreateGame { width, height, .. } = &mut msg {
+            (*width, *height) = (2, 2);
+        }
+        let err = app.execute_contract("owner".into_addr(), game_addr.clone(), &msg, &[]).unwrap_err();
+        assert_eq!(ContractError::InvalidShips {  }, err.downcast().unwrap());
+
+        // a fleet that only fits one way is still accepted
+        app.execute_contract("owner".into_addr(), game_addr, &mock_create_game_msg(vec![1, 1, 1, 1, 1]), &[]).unwrap();
+    }
+
+    #[test]
+    fn update_config() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (_, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr);
+
+        let update = |fee_percentage, reward_percentage| ExecuteMsg::UpdateConfig {
+            admin: None,
+            min_stake: Some(Uint128::new(100)),
+            fee_percentage: Some(fee_percentage),
+            reward_percentage: Some(reward_percentage),
+            turn_duration: None,
+            fee_route: None,
+            treasury: None,
+            native_denom: None,
+            native_min_stake: None,
+            betting_moves: None,
+        };
 
         let err = app
-            .instantiate_contract(
-                code_id, 
-                "owner".into_addr(),
-                &mock_instantiate_msg(0, "token".into_addr()), 
-                &[], 
-                "Contract", 
-                None
-            ).unwrap_err();
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &update(10, 2), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {  }, err.downcast().unwrap());
 
-        assert_eq!(ContractError::InvalidShips {  }, err.downcast().unwrap())
-    }
+        let err = app
+            .execute_contract("owner".into_addr(), game_addr.clone(), &update(60, 50), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidConfig {  }, err.downcast().unwrap());
 
-    // #[test]
-    // fn should_throw_invalid_board_error() {
-    //     let mut app = App::default();
+        app.execute_contract("owner".into_addr(), game_addr.clone(), &update(10, 2), &[])
+            .unwrap();
 
-    //     let code = ContractWrapper::new(execute, instantiate, query);
-    //     let code_id = app.store_code(Box::new(code));
+        let config: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetConfig {})
+            .unwrap();
+        assert_eq!(config.admin, "owner".into_addr());
+        assert_eq!(config.min_stake, Uint128::new(100));
+        assert_eq!(config.fee_percentage, 10);
+        assert_eq!(config.reward_percentage, 2);
+        assert_eq!(config.turn_duration, 60);
+
+        // the running game keeps the parameters it was created with
+        let game_config: GameConfigResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameConfig { game_id: 1 })
+            .unwrap();
+        assert_eq!(game_config.fee_percentage, 5);
+        assert_eq!(game_config.reward_percentage, 1);
 
-    //     let err = app
-    //         .instantiate_contract(
-    //             code_id, 
-    //             "owner".into_addr(),
-    //             &mock_instantiate_msg(3, "token".into_addr()), 
-    //             &[], 
-    //             "Contract", 
-    //             None
-    //         ).unwrap_err();
+        let game_id = create_and_join_game(&mut app, &game_addr, vec![1]);
+        let game_config: GameConfigResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameConfig { game_id })
+            .unwrap();
+        assert_eq!(game_config.fee_percentage, 10);
+        assert_eq!(game_config.reward_percentage, 2);
 
-    //     assert_eq!(ContractError::InvalidBoard {  }, err.downcast().unwrap())
-    // }
+        let err = app
+            .execute_contract(
+                player1_addr,
+                game_addr,
+                &ExecuteMsg::CreateGame {
+                    stake: Uint128::new(50),
+                    fleet: vec![1],
+                    width: 3,
+                    height: 3,
+                    turn_duration: None,
+                    board: player1_board().root(),
+                    invitee: None,
+                    min_rating: None,
+                    max_rating: None,
+                    token_address: None,
+                    denom: None,
+                    proof_format: None,
+                    salvo: None,
+                    extra_turn: None,
+                    weapons: None,
+                },
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidStake {  }, err.downcast().unwrap());
+    }
 
     #[test]
-    fn game() {
+    fn accrued_fees() {
         let player1_addr = "player1".into_addr();
         let player2_addr = "player2".into_addr();
-        let (cw20_addr, game_addr, mut app) = init_app(
-            player1_addr.clone(),
-            player2_addr.clone()
-        );
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr);
 
-        // start game
-        let response = app
-            .execute_contract(
-                player1_addr.clone(), 
-                game_addr.clone(), 
-                &ExecuteMsg::StartGame {}, 
-                &[]
-        ).unwrap();
+        settle_by_timeout(&mut app, &cw20_addr, &game_addr, 1);
 
-        let wasm = response
-            .events.iter()
-            .find(|ev| ev.ty == "wasm")
+        let response: AccruedFeesResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetAccruedFees {})
             .unwrap();
         assert_eq!(
-            wasm.attributes
-                .iter()
-                .find(|attr| attr.key == "action")
-                .unwrap()
-                .value,
-            "start_game"
-        );
-        assert_eq!(
-            Uint128::from_str(&wasm.attributes
-                .iter()
-                .find(|attr| attr.key == "stake")
-                .unwrap()
-                .value 
-            ).unwrap(),
-            Uint128::new(1000)
+            response.fees,
+            vec![AccruedFee { asset: StakeAsset::Cw20 { address: cw20_addr.clone() }, amount: Uint128::new(100) }]
         );
 
-        let contract_balance: BalanceResponse = app.wrap()
-            .query_wasm_smart(
-                cw20_addr.clone(),
-                &Cw20QueryMsg::Balance { 
-                    address: game_addr.to_string() 
-                } 
-        ).unwrap();
-        assert_eq!(contract_balance.balance, Uint128::new(2000));
+        let withdraw = |amount| ExecuteMsg::WithdrawFees {
+            token_address: None,
+            denom: None,
+            recipient: "treasury".into_addr().to_string(),
+            amount: Uint128::new(amount),
+        };
 
-        let player1_balance: BalanceResponse = app.wrap()
+        let err = app
+            .execute_contract(player1_addr, game_addr.clone(), &withdraw(60), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {  }, err.downcast().unwrap());
+
+        let err = app
+            .execute_contract("owner".into_addr(), game_addr.clone(), &withdraw(101), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::InsufficientFees {  }, err.downcast().unwrap());
+
+        app.execute_contract("owner".into_addr(), game_addr.clone(), &withdraw(60), &[])
+            .unwrap();
+
+        let treasury_balance: BalanceResponse = app
+            .wrap()
             .query_wasm_smart(
                 cw20_addr.clone(),
-                &Cw20QueryMsg::Balance {
-                    address: player1_addr.clone().to_string(),
-                },
+                &Cw20QueryMsg::Balance { address: "treasury".into_addr().to_string() }
+            )
+            .unwrap();
+        assert_eq!(treasury_balance.balance, Uint128::new(60));
+
+        let response: AccruedFeesResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr, &QueryMsg::GetAccruedFees {})
+            .unwrap();
+        assert_eq!(response.fees[0].amount, Uint128::new(40));
+    }
+
+    #[test]
+    fn routed_fees() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr, player2_addr);
+
+        let route = |fee_route, treasury: Option<String>| ExecuteMsg::UpdateConfig {
+            admin: None,
+            min_stake: None,
+            fee_percentage: None,
+            reward_percentage: None,
+            turn_duration: None,
+            fee_route: Some(fee_route),
+            treasury,
+            native_denom: None,
+            native_min_stake: None,
+            betting_moves: None,
+        };
+
+        let err = app
+            .execute_contract("owner".into_addr(), game_addr.clone(), &route(FeeRoute::Treasury, None), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidConfig {  }, err.downcast().unwrap());
+
+        app.execute_contract(
+            "owner".into_addr(),
+            game_addr.clone(),
+            &route(FeeRoute::Treasury, Some("treasury".into_addr().to_string())),
+            &[]
         ).unwrap();
-        assert_eq!(player1_balance.balance, Uint128::new(1000000 - 1000));
 
-        let player1_balance: BalanceResponse = app.wrap()
+        settle_by_timeout(&mut app, &cw20_addr, &game_addr, 1);
+
+        let treasury_balance: BalanceResponse = app
+            .wrap()
             .query_wasm_smart(
                 cw20_addr.clone(),
-                &Cw20QueryMsg::Balance {
-                    address: player2_addr.clone().to_string(),
-                },
-        ).unwrap();
-        assert_eq!(player1_balance.balance, Uint128::new(1000000 - 1000));
-
-        // player1's turn
-        let response = app
-            .execute_contract(
-                "player1".into_addr(),
-                game_addr.clone(),
-                &ExecuteMsg::Play { 
-                    field: (1, 0),
-                    value: false,
-                    proof: vec![
-                        ProofStep {
-                            hash: "fcbcf165908dd18a9e49f7ff27810176db8e9f63b4352213741664245224f8aa".to_owned(),
-                            is_left: true
-                        },
-                        ProofStep {
-                            hash: "b39595dabdf67f2d2b1c22e6690c8500c89ccb9d817f1cce4b47337910cbe2cb".to_owned(),
-                            is_left: true
-                        },
-                        ProofStep {
-                            hash: "9a74c4cb6669420048ea661a3b8e501ca5c10c2f2680a41acc2128422c1ff6b6".to_owned(),
-                            is_left: false
-                        },
-                        ProofStep {
-                            hash: "2e176322075537fd763b4405a4392854d0f67b079f814bbe9d9683ca371343e9".to_owned(),
-                            is_left: false
-                        },
-                    ] 
-                },
-                &[]
+                &Cw20QueryMsg::Balance { address: "treasury".into_addr().to_string() }
             )
             .unwrap();
+        assert_eq!(treasury_balance.balance, Uint128::new(100));
 
-        let wasm = response
-            .events.iter()
-            .find(|ev| ev.ty == "wasm")
+        app.execute_contract("owner".into_addr(), game_addr.clone(), &route(FeeRoute::Burn, None), &[])
             .unwrap();
-        assert_eq!(
-            wasm.attributes
-                .iter()
-                .find(|attr| attr.key == "action")
-                .unwrap()
-                .value,
-            "play"
-        );
-        
-        let ship_missed: Vec<_> = response
-            .events
-            .iter()
-            .filter(|ev| ev.ty == "wasm-ship_missed")
-            .collect();
-        assert_eq!(
-            ship_missed[0]
-                .attributes
-                .iter()
-                .find(|attr| attr.key == "missed")
-                .unwrap()
-                .value,
-            stringify!((1, 0))
-        );
 
-        // player2's turn
-        let response = app
-            .execute_contract(
-                "player2".into_addr(),
-                game_addr.clone(),
-                &ExecuteMsg::Play { 
-                    field: (1, 1),
+        let game_id = create_and_join_game(&mut app, &game_addr, vec![1]);
+        settle_by_timeout(&mut app, &cw20_addr, &game_addr, game_id);
+
+        // two rewards of 19 were minted and the second fee of 100 burned
+        let token_info: cw20::TokenInfoResponse = app
+            .wrap()
+            .query_wasm_smart(cw20_addr.clone(), &Cw20QueryMsg::TokenInfo {})
+            .unwrap();
+        assert_eq!(token_info.total_supply, Uint128::new(2_000_000 + 2 * 19 - 100));
+
+        let contract_balance: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(cw20_addr, &Cw20QueryMsg::Balance { address: game_addr.to_string() })
+            .unwrap();
+        assert_eq!(contract_balance.balance, Uint128::zero());
+
+        let response: AccruedFeesResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr, &QueryMsg::GetAccruedFees {})
+            .unwrap();
+        assert_eq!(response.fees, vec![]);
+    }
+
+    #[test]
+    fn native_stakes() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+        for player in [&player1_addr, &player2_addr] {
+            app.sudo(SudoMsg::Bank(BankSudo::Mint { to_address: player.to_string(), amount: coins(5000, "ucosm") }))
+                .unwrap();
+        }
+
+        let create_game = |denom: &str| ExecuteMsg::CreateGame {
+            stake: Uint128::new(1000),
+            fleet: vec![1],
+            width: 3,
+            height: 3,
+            turn_duration: None,
+            board: player1_board().root(),
+            invitee: None,
+            min_rating: None,
+            max_rating: None,
+            token_address: None,
+            denom: Some(denom.to_string()),
+            proof_format: None,
+            salvo: None,
+            extra_turn: None,
+            weapons: None,
+        };
+
+        // native stakes are disabled until the admin sets a denom
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &create_game("ucosm"), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidToken {  }, err.downcast().unwrap());
+
+        app.execute_contract(
+            "owner".into_addr(),
+            game_addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                admin: None,
+                min_stake: None,
+                fee_percentage: None,
+                reward_percentage: None,
+                turn_duration: None,
+                fee_route: None,
+                treasury: None,
+                native_denom: Some("ucosm".to_string()),
+                native_min_stake: Some(Uint128::new(100)),
+                betting_moves: None,
+            },
+            &[]
+        ).unwrap();
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &create_game("ucosm"), &[])
+            .unwrap();
+        let game_id = 2;
+
+        let config: GameConfigResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameConfig { game_id })
+            .unwrap();
+        assert_eq!(config.asset, StakeAsset::Native { denom: "ucosm".to_string() });
+        assert_eq!(config.reward_percentage, 0);
+
+        // the stake is sent along with joining
+        app.execute_contract(
+            player2_addr.clone(),
+            game_addr.clone(),
+            &mock_join_game_msg(game_id),
+            &coins(1000, "ucosm")
+        ).unwrap();
+
+        let deposit = |app: &mut App, game_id, amount| app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::Deposit { game_id },
+            &coins(amount, "ucosm")
+        );
+        let err = deposit(&mut app, game_id, 500).unwrap_err();
+        assert_eq!(ContractError::InvalidStake {  }, err.downcast().unwrap());
+        let err = deposit(&mut app, 1, 1000).unwrap_err();
+        assert_eq!(ContractError::InvalidToken {  }, err.downcast().unwrap());
+        let err = send_stake(&mut app, &cw20_addr, &game_addr, &player1_addr, 1000, &ReceiveMsg::Deposit { game_id })
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidToken {  }, err.downcast().unwrap());
+
+        deposit(&mut app, game_id, 1000).unwrap();
+        let state: GameStateResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id })
+            .unwrap();
+        assert!(state.started);
+
+        app.update_block(|b| b.time = b.time.plus_seconds(1000));
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::TimeoutWin { game_id }, &[])
+            .unwrap();
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &player2_board().reveal(game_id), &[])
+            .unwrap();
+        app.update_block(|b| b.time = b.time.plus_seconds(301));
+        let token_supply = |app: &App| app
+            .wrap()
+            .query_wasm_smart::<cw20::TokenInfoResponse>(cw20_addr.clone(), &Cw20QueryMsg::TokenInfo {})
+            .unwrap()
+            .total_supply;
+        let supply = token_supply(&app);
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::Settle { game_id }, &[])
+            .unwrap();
+
+        // the pot minus the 5% fee is paid in the bank denom and no reward is minted
+        let bank_balance = |address: &Addr| app.wrap().query_balance(address, "ucosm").unwrap().amount;
+        assert_eq!(bank_balance(&player2_addr), Uint128::new(5900));
+        assert_eq!(bank_balance(&player1_addr), Uint128::new(4000));
+        assert_eq!(token_supply(&app), supply);
+
+        let response: AccruedFeesResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetAccruedFees {})
+            .unwrap();
+        assert_eq!(
+            response.fees,
+            vec![AccruedFee { asset: StakeAsset::Native { denom: "ucosm".to_string() }, amount: Uint128::new(100) }]
+        );
+
+        app.execute_contract(
+            "owner".into_addr(),
+            game_addr.clone(),
+            &ExecuteMsg::WithdrawFees {
+                token_address: None,
+                denom: Some("ucosm".to_string()),
+                recipient: "treasury".into_addr().to_string(),
+                amount: Uint128::new(100),
+            },
+            &[]
+        ).unwrap();
+        assert_eq!(
+            app.wrap().query_balance("treasury".into_addr(), "ucosm").unwrap().amount,
+            Uint128::new(100)
+        );
+    }
+
+    #[test]
+    fn partner_tokens() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        let cw20_code_id = app.store_code(Box::new(ContractWrapper::new(cw20_execute, cw20_instantiate, cw20_query)));
+        let partner_addr = app
+            .instantiate_contract(
+                cw20_code_id,
+                "owner".into_addr(),
+                &mock_cw20_instantiate_msg(player1_addr.clone(), player2_addr.clone(), "admin".into_addr()),
+                &[],
+                "partner-token",
+                None
+            )
+            .unwrap();
+
+        let create_game = |stake| ExecuteMsg::CreateGame {
+            stake: Uint128::new(stake),
+            fleet: vec![1],
+            width: 3,
+            height: 3,
+            turn_duration: None,
+            board: player1_board().root(),
+            invitee: None,
+            min_rating: None,
+            max_rating: None,
+            token_address: Some(partner_addr.to_string()),
+            denom: None,
+            proof_format: None,
+            salvo: None,
+            extra_turn: None,
+            weapons: None,
+        };
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &create_game(1000), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::TokenNotAccepted {  }, err.downcast().unwrap());
+
+        // the contract is not the partner token's minter, so no reward is minted in it
+        let add_token = ExecuteMsg::AddToken {
+            token_address: partner_addr.to_string(),
+            min_stake: Uint128::new(200),
+            mint_reward: false,
+        };
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &add_token, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {  }, err.downcast().unwrap());
+        app.execute_contract("owner".into_addr(), game_addr.clone(), &add_token, &[])
+            .unwrap();
+
+        let response: AcceptedTokensResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetAcceptedTokens { start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(response.tokens, vec![AcceptedTokenInfo {
+            token_address: partner_addr.clone(),
+            min_stake: Uint128::new(200),
+            mint_reward: false,
+        }]);
+
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &create_game(100), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidStake {  }, err.downcast().unwrap());
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &create_game(1000), &[])
+            .unwrap();
+        let game_id = 2;
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &mock_join_game_msg(game_id), &[])
+            .unwrap();
+
+        let config: GameConfigResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameConfig { game_id })
+            .unwrap();
+        assert_eq!(config.asset, StakeAsset::Cw20 { address: partner_addr.clone() });
+        assert_eq!(config.reward_percentage, 0);
+
+        // stakes have to be sent through the token the game was created with
+        let err = send_stake(&mut app, &cw20_addr, &game_addr, &player1_addr, 1000, &ReceiveMsg::Deposit { game_id })
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidToken {  }, err.downcast().unwrap());
+
+        let partner_balance = balance(&app, &partner_addr, &player2_addr);
+        settle_by_timeout(&mut app, &partner_addr, &game_addr, game_id);
+        assert_eq!(balance(&app, &partner_addr, &player2_addr), partner_balance + Uint128::new(900));
+
+        let response: AccruedFeesResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetAccruedFees {})
+            .unwrap();
+        assert!(response.fees.contains(
+            &AccruedFee { asset: StakeAsset::Cw20 { address: partner_addr.clone() }, amount: Uint128::new(100) }
+        ));
+
+        // removing the token only affects new games
+        app.execute_contract(
+            "owner".into_addr(),
+            game_addr.clone(),
+            &ExecuteMsg::RemoveToken { token_address: partner_addr.to_string() },
+            &[]
+        ).unwrap();
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &create_game(1000), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::TokenNotAccepted {  }, err.downcast().unwrap());
+    }
+
+    #[test]
+    fn betting() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+        let alice_addr = "alice".into_addr();
+        let bob_addr = "bob".into_addr();
+        for spectator in [&alice_addr, &bob_addr] {
+            app.execute_contract(
+                player1_addr.clone(),
+                cw20_addr.clone(),
+                &Cw20ExecuteMsg::Transfer { recipient: spectator.to_string(), amount: Uint128::new(1000) },
+                &[]
+            ).unwrap();
+        }
+
+        let bet = |app: &mut App, bettor: &Addr, player: &Addr, amount| send_stake(
+            app,
+            &cw20_addr,
+            &game_addr,
+            bettor,
+            amount,
+            &ReceiveMsg::PlaceBet { game_id: 1, player: player.to_string() }
+        );
+        let err = bet(&mut app, &player1_addr, &player2_addr, 100).unwrap_err();
+        assert_eq!(ContractError::BetAgainstSelf {  }, err.downcast().unwrap());
+        bet(&mut app, &player1_addr, &player1_addr, 100).unwrap();
+        bet(&mut app, &alice_addr, &player2_addr, 300).unwrap();
+        bet(&mut app, &bob_addr, &player1_addr, 100).unwrap();
+        let err = bet(&mut app, &alice_addr, &player1_addr, 100).unwrap_err();
+        assert_eq!(ContractError::BetOnOpponent {  }, err.downcast().unwrap());
+
+        // a bettor cannot join the game they bet on as the creator's opponent
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &mock_create_game_msg(vec![1]), &[]).unwrap();
+        send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &bob_addr,
+            100,
+            &ReceiveMsg::PlaceBet { game_id: 2, player: player1_addr.to_string() }
+        ).unwrap();
+        let err = app
+            .execute_contract(bob_addr.clone(), game_addr.clone(), &mock_join_game_msg(2), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::BetAgainstSelf {  }, err.downcast().unwrap());
+
+        let bets = |app: &App| -> BetsResponse {
+            app.wrap()
+                .query_wasm_smart(game_addr.clone(), &QueryMsg::GetBets { game_id: 1 })
+                .unwrap()
+        };
+        let response: BetResponse = app
+            .wrap()
+            .query_wasm_smart(
+                game_addr.clone(),
+                &QueryMsg::GetBet { game_id: 1, bettor: alice_addr.to_string() }
+            )
+            .unwrap();
+        assert_eq!(response, BetResponse { player: player2_addr.clone(), amount: Uint128::new(300) });
+
+        let response = bets(&app);
+        assert!(response.open);
+        assert_eq!(response.totals.len(), 2);
+        assert!(response.totals.contains(&BetTotal { player: player1_addr.clone(), amount: Uint128::new(200) }));
+        assert!(response.totals.contains(&BetTotal { player: player2_addr.clone(), amount: Uint128::new(300) }));
+
+        // the betting window is fixed when a game is created, a shorter one only closes new games
+        app.execute_contract(
+            "owner".into_addr(),
+            game_addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                admin: None,
+                min_stake: None,
+                fee_percentage: None,
+                reward_percentage: None,
+                turn_duration: None,
+                fee_route: None,
+                treasury: None,
+                native_denom: None,
+                native_min_stake: None,
+                betting_moves: Some(0),
+            },
+            &[]
+        ).unwrap();
+        assert!(bets(&app).open);
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &mock_create_game_msg(vec![1]), &[]).unwrap();
+        let err = send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &alice_addr,
+            100,
+            &ReceiveMsg::PlaceBet { game_id: 3, player: player1_addr.to_string() }
+        ).unwrap_err();
+        assert_eq!(ContractError::BettingClosed {  }, err.downcast().unwrap());
+
+        let claim = |app: &mut App, bettor: &Addr| app.execute_contract(
+            bettor.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::ClaimBet { game_id: 1 },
+            &[]
+        );
+        let err = claim(&mut app, &alice_addr).unwrap_err();
+        assert_eq!(ContractError::GameNotFinished {  }, err.downcast().unwrap());
+
+        settle_by_timeout(&mut app, &cw20_addr, &game_addr, 1);
+
+        // alice gets her bet back and the 200 bet on player1 minus the 5% house fee
+        claim(&mut app, &alice_addr).unwrap();
+        assert_eq!(balance(&app, &cw20_addr, &alice_addr), Uint128::new(1000 + 190));
+        claim(&mut app, &bob_addr).unwrap();
+        // bob lost his bet on game 1, his bet on the open game 2 is still held
+        assert_eq!(balance(&app, &cw20_addr, &bob_addr), Uint128::new(800));
+        let err = claim(&mut app, &alice_addr).unwrap_err();
+        assert_eq!(ContractError::NoBet {  }, err.downcast().unwrap());
+
+        // the game fee of 100 and the house fee of 10
+        let response: AccruedFeesResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetAccruedFees {})
+            .unwrap();
+        assert_eq!(response.fees[0].amount, Uint128::new(110));
+    }
+
+    #[test]
+    fn should_throw_invalid_board_error() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (_, game_addr, mut app) = init_app(player1_addr, player2_addr);
+
+        for (width, height) in [(0, 3), (3, 0), (21, 10)] {
+            let err = app
+                .execute_contract(
+                    "owner".into_addr(),
+                    game_addr.clone(),
+                    &ExecuteMsg::CreateGame {
+                        stake: Uint128::new(1000),
+                        fleet: vec![1],
+                        width,
+                        height,
+                        turn_duration: None,
+                        board: player1_board().root(),
+                        invitee: None,
+                        min_rating: None,
+                        max_rating: None,
+                        token_address: None,
+                        denom: None,
+                        proof_format: None,
+                        salvo: None,
+                        extra_turn: None,
+                        weapons: None,
+                    },
+                    &[]
+                ).unwrap_err();
+
+            assert_eq!(ContractError::InvalidBoard {  }, err.downcast().unwrap())
+        }
+    }
+
+    #[test]
+    fn classic_board_and_fleet() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (_, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr);
+
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::CreateGame {
+                stake: Uint128::new(1000),
+                fleet: vec![5, 4, 3, 3, 2],
+                width: 10,
+                height: 10,
+                turn_duration: None,
+                board: player1_board().root(),
+                invitee: None,
+                min_rating: None,
+                max_rating: None,
+                token_address: None,
+                denom: None,
+                proof_format: None,
+                salvo: None,
+                extra_turn: None,
+                weapons: None,
+            },
+            &[]
+        ).unwrap();
+
+        let response: GameConfigResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameConfig { game_id: 2 })
+            .unwrap();
+        assert_eq!(response.fleet, vec![5, 4, 3, 3, 2]);
+        assert_eq!(response.fleet_size, 17);
+        assert_eq!((response.width, response.height), (10, 10));
+    }
+
+    #[test]
+    fn game_is_won_after_sinking_whole_fleet() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        let player1_board = MockBoard::new(
+            vec![
+                vec![true, false, false],
+                vec![true, false, false],
+                vec![false, false, false],
+            ],
+            "player1",
+        );
+        let player2_board = MockBoard::new(
+            vec![
+                vec![false, false, false],
+                vec![false, false, false],
+                vec![false, true, true],
+            ],
+            "player2",
+        );
+
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::CreateGame {
+                stake: Uint128::new(1000),
+                fleet: vec![2],
+                width: 3,
+                height: 3,
+                turn_duration: None,
+                board: player1_board.root(),
+                invitee: None,
+                min_rating: None,
+                max_rating: None,
+                token_address: None,
+                denom: None,
+                proof_format: None,
+                salvo: None,
+                extra_turn: None,
+                weapons: None,
+            },
+            &[]
+        ).unwrap();
+
+        send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player1_addr,
+            1000,
+            &ReceiveMsg::Deposit { game_id: 2 }
+        ).unwrap();
+
+        send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player2_addr,
+            1000,
+            &ReceiveMsg::JoinGame { game_id: 2, board: player2_board.root() }
+        ).unwrap();
+
+        let response = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &player2_board.play(2, (2, 1)), &[])
+            .unwrap();
+        assert!(response.events.iter().any(|ev| ev.ty == "wasm-ship_sank"));
+        assert!(!response.events.iter().any(|ev| ev.ty == "wasm-game_won"));
+
+        app
+            .execute_contract(player2_addr.clone(), game_addr.clone(), &player1_board.play(2, (2, 2)), &[])
+            .unwrap();
+
+        let response = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &player2_board.play(2, (2, 2)), &[])
+            .unwrap();
+        assert!(response.events.iter().any(|ev| ev.ty == "wasm-game_won"));
+
+        let response: GameStateResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 2 })
+            .unwrap();
+        assert!(response.finished);
+    }
+
+    #[test]
+    fn illegal_board_is_slashed() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        // player1 commits a board without any ship and can never lose
+        let empty_board = MockBoard::new(vec![vec![false; 3]; 3], "player1");
+
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::CreateGame {
+                stake: Uint128::new(1000),
+                fleet: vec![1],
+                width: 3,
+                height: 3,
+                turn_duration: None,
+                board: empty_board.root(),
+                invitee: None,
+                min_rating: None,
+                max_rating: None,
+                token_address: None,
+                denom: None,
+                proof_format: None,
+                salvo: None,
+                extra_turn: None,
+                weapons: None,
+            },
+            &[]
+        ).unwrap();
+        send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player1_addr,
+            1000,
+            &ReceiveMsg::Deposit { game_id: 2 }
+        ).unwrap();
+        send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player2_addr,
+            1000,
+            &ReceiveMsg::JoinGame { game_id: 2, board: player2_board().root() }
+        ).unwrap();
+
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &player2_board().play(2, (0, 2)), &[])
+            .unwrap();
+
+        let response = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &empty_board.reveal(2), &[])
+            .unwrap();
+        let wasm = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap();
+        assert_eq!(
+            wasm.attributes
+                .iter()
+                .find(|attr| attr.key == "legal")
+                .unwrap()
+                .value,
+            "false"
+        );
+
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &player2_board().reveal(2), &[])
+            .unwrap();
+
+        let response = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Settle { game_id: 2 },
+                &[]
+            )
+            .unwrap();
+
+        let slashed = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm-slashed")
+            .unwrap();
+        assert_eq!(
+            slashed.attributes
+                .iter()
+                .find(|attr| attr.key == "player")
+                .unwrap()
+                .value,
+            player1_addr.to_string()
+        );
+
+        let wasm = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap();
+        assert_eq!(
+            wasm.attributes
+                .iter()
+                .find(|attr| attr.key == "recipient")
+                .unwrap()
+                .value,
+            player2_addr.to_string()
+        );
+
+        let player2_balance: BalanceResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: player2_addr.to_string(),
+                },
+        ).unwrap();
+        assert_eq!(player2_balance.balance, Uint128::new(1_000_000 - 1_000 + 1_900));
+
+        let err = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Settle { game_id: 2 },
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::AlreadySettled {  }, err.downcast().unwrap());
+    }
+
+    #[test]
+    fn roots_are_stored_in_lowercase() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        let create_game = |board: String| ExecuteMsg::CreateGame {
+            stake: Uint128::new(1000),
+            fleet: vec![1],
+            width: 3,
+            height: 3,
+            turn_duration: None,
+            board,
+            invitee: None,
+            min_rating: None,
+            max_rating: None,
+            token_address: None,
+            denom: None,
+            proof_format: None,
+            salvo: None,
+            extra_turn: None,
+            weapons: None,
+        };
+        for board in [String::from("root"), player1_board().root()[2..].to_string()] {
+            let err = app
+                .execute_contract(player1_addr.clone(), game_addr.clone(), &create_game(board), &[])
+                .unwrap_err();
+            assert_eq!(ContractError::InvalidRoot {  }, err.downcast().unwrap());
+        }
+
+        // an uppercase root is accepted and the board can still be revealed
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &create_game(player1_board().root().to_uppercase()),
+            &[]
+        ).unwrap();
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &mock_join_game_msg(2), &[]).unwrap();
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 2);
+
+        let players: PlayersResponse = app.wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetPlayers { game_id: 2 })
+            .unwrap();
+        let player1 = players.players.iter().find(|player| player.address == player1_addr).unwrap();
+        assert_eq!(player1.board_root, player1_board().root());
+
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &player2_board().play(2, (0, 0)), &[])
+            .unwrap();
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &player1_board().play(2, (0, 0)), &[])
+            .unwrap();
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::Resign { game_id: 2 }, &[])
+            .unwrap();
+        let response = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &player1_board().reveal(2), &[])
+            .unwrap();
+        assert!(response.has_event(&Event::new("wasm").add_attribute("legal", "true")));
+    }
+
+    #[test]
+    fn winner_reveal_is_optional() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+        let game_id = create_and_join_game(&mut app, &game_addr, vec![1]);
+        for game_id in [1, game_id] {
+            deposit_stakes(&mut app, &cw20_addr, &game_addr, game_id);
+        }
+
+        // player1 lets both games run out, and only reveals in the second one
+        app.update_block(|b| b.time = b.time.plus_seconds(61));
+        for game_id in [1, game_id] {
+            app.execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::TimeoutWin { game_id }, &[])
+                .unwrap();
+        }
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &player1_board().reveal(game_id), &[])
+            .unwrap();
+        app.update_block(|b| b.time = b.time.plus_seconds(301));
+
+        let settle = |app: &mut App, game_id| app
+            .execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::Settle { game_id }, &[])
+            .unwrap();
+        let slashed = |response: &AppResponse| -> Vec<String> {
+            response.events.iter()
+                .filter(|ev| ev.ty == "wasm-slashed")
+                .flat_map(|ev| ev.attributes.iter().filter(|attr| attr.key == "player"))
+                .map(|attr| attr.value.clone())
+                .collect()
+        };
+
+        // the winner keeps the pot without revealing while the loser shows no legal board
+        let response = settle(&mut app, 1);
+        assert!(response.has_event(&Event::new("wasm").add_attribute("recipient", player2_addr.to_string())));
+        assert_eq!(slashed(&response), vec![player1_addr.to_string()]);
+
+        // against a legal board the winner has to reveal to keep it
+        let response = settle(&mut app, game_id);
+        assert!(response.has_event(&Event::new("wasm").add_attribute("recipient", player1_addr.to_string())));
+        assert_eq!(slashed(&response), vec![player2_addr.to_string()]);
+    }
+
+    #[test]
+    fn should_throw_invalid_reveal_error() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &player1_board().reveal(1), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::GameNotFinished {  }, err.downcast().unwrap());
+
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &player2_board().play(1, (0, 2)), &[])
+            .unwrap();
+
+        // a board that differs from the committed one in a single field
+        let mut board = player1_board();
+        board.fields[1][1] = false;
+        board.fields[0][0] = true;
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &board.reveal(1), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidReveal {  }, err.downcast().unwrap());
+
+        // a board with the wrong dimensions
+        let err = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Reveal {
+                    game_id: 1,
+                    board: vec![vec![false; 3]; 2],
+                    salts: player1_board().salts[..2].to_vec(),
+                },
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidReveal {  }, err.downcast().unwrap());
+
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &player1_board().reveal(1), &[])
+            .unwrap();
+
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &player1_board().reveal(1), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::AlreadyRevealed {  }, err.downcast().unwrap());
+    }
+
+    #[test]
+    fn should_throw_invalid_field_error() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        let board = player2_board();
+        for field in [(3, 0), (0, 3), (1, 5)] {
+            let err = app
+                .execute_contract(
+                    player1_addr.clone(),
+                    game_addr.clone(),
+                    &ExecuteMsg::Play {
+                        game_id: 1,
+                        field,
+                        value: false,
+                        salt: board.salt((0, 0)),
+                        proof: board.proof((0, 0))
+                    },
+                    &[]
+                )
+                .unwrap_err();
+
+            assert_eq!(ContractError::InvalidField {  }, err.downcast().unwrap());
+        }
+    }
+
+    #[test]
+    fn game() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(
+            player1_addr.clone(),
+            player2_addr.clone()
+        );
+
+        // deposit stakes, the second deposit starts the game
+        let response = send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player1_addr,
+            1000,
+            &ReceiveMsg::Deposit { game_id: 1 }
+        ).unwrap();
+        assert!(!response.events.iter().any(|ev| ev.ty == "wasm-game_started"));
+
+        let response = send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player2_addr,
+            1000,
+            &ReceiveMsg::Deposit { game_id: 1 }
+        ).unwrap();
+
+        let wasm = response
+            .events.iter()
+            .find(|ev| {
+                ev.ty == "wasm" && ev.attributes
+                    .iter()
+                    .any(|attr| attr.key == "_contract_address" && attr.value == game_addr.as_str())
+            })
+            .unwrap();
+        assert_eq!(
+            wasm.attributes
+                .iter()
+                .find(|attr| attr.key == "action")
+                .unwrap()
+                .value,
+            "deposit"
+        );
+
+        let game_started = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm-game_started")
+            .unwrap();
+        assert_eq!(
+            Uint128::from_str(&game_started.attributes
+                .iter()
+                .find(|attr| attr.key == "stake")
+                .unwrap()
+                .value 
+            ).unwrap(),
+            Uint128::new(1000)
+        );
+
+        let contract_balance: BalanceResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance { 
+                    address: game_addr.to_string() 
+                } 
+        ).unwrap();
+        assert_eq!(contract_balance.balance, Uint128::new(2000));
+
+        let player1_balance: BalanceResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: player1_addr.clone().to_string(),
+                },
+        ).unwrap();
+        assert_eq!(player1_balance.balance, Uint128::new(1000000 - 1000));
+
+        let player1_balance: BalanceResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: player2_addr.clone().to_string(),
+                },
+        ).unwrap();
+        assert_eq!(player1_balance.balance, Uint128::new(1000000 - 1000));
+
+        // player1's turn
+        let response = app
+            .execute_contract(
+                "player1".into_addr(),
+                game_addr.clone(),
+                &player2_board().play(1, (1, 0)),
+                &[]
+            )
+            .unwrap();
+
+        let wasm = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap();
+        assert_eq!(
+            wasm.attributes
+                .iter()
+                .find(|attr| attr.key == "action")
+                .unwrap()
+                .value,
+            "play"
+        );
+        
+        let ship_missed: Vec<_> = response
+            .events
+            .iter()
+            .filter(|ev| ev.ty == "wasm-ship_missed")
+            .collect();
+        assert_eq!(
+            ship_missed[0]
+                .attributes
+                .iter()
+                .find(|attr| attr.key == "missed")
+                .unwrap()
+                .value,
+            stringify!((1, 0))
+        );
+
+        // player2's turn
+        let response = app
+            .execute_contract(
+                "player2".into_addr(),
+                game_addr.clone(),
+                &player1_board().play(1, (1, 1)),
+                &[]
+            )
+            .unwrap();
+
+        let wasm = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap();
+        assert_eq!(
+            wasm.attributes
+                .iter()
+                .find(|attr| attr.key == "action")
+                .unwrap()
+                .value,
+            "play"
+        );
+
+        let wasm = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap();
+        assert_eq!(
+            wasm.attributes
+                .iter()
+                .find(|attr| attr.key == "winner")
+                .unwrap()
+                .value,
+            player2_addr.clone().to_string()
+        );
+
+        let game_won: Vec<_> = response
+            .events
+            .iter()
+            .filter(|ev| ev.ty == "wasm-game_won")
+            .collect();
+        assert_eq!(
+            game_won[0]
+                .attributes
+                .iter()
+                .find(|attr| attr.key == "sank")
+                .unwrap()
+                .value,
+            stringify!((1, 1))
+        );
+
+        // stakes stay in escrow until the boards are revealed
+        let contract_balance: cw20::BalanceResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: game_addr.to_string(),
+            }
+        ).unwrap();
+        assert_eq!(contract_balance.balance, Uint128::new(2000));
+
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &player1_board().reveal(1), &[])
+            .unwrap();
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &player2_board().reveal(1), &[])
+            .unwrap();
+
+        let response = app
+            .execute_contract(
+                "anyone".into_addr(),
+                game_addr.clone(),
+                &ExecuteMsg::Settle { game_id: 1 },
+                &[]
+            )
+            .unwrap();
+
+        let wasm = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap();
+        assert_eq!(
+            wasm.attributes
+                .iter()
+                .find(|attr| attr.key == "recipient")
+                .unwrap()
+                .value,
+            player2_addr.clone().to_string()
+        );
+
+        assert_eq!(
+            Uint128::from_str(
+                &wasm.attributes
+                .iter()
+                .find(|a| a.key == "payout")
+                .unwrap()
+                .value
+            ).unwrap(),
+            Uint128::new(1900)
+        );
+
+        assert_eq!(
+            Uint128::from_str(
+                &wasm.attributes
+                .iter()
+                .find(|a| a.key == "fee_retained")
+                .unwrap()
+                .value
+            ).unwrap(),
+            Uint128::new(100)
+        );
+
+        assert_eq!(
+            Uint128::from_str(
+                &wasm.attributes
+                .iter()
+                .find(|a| a.key == "minted_reward")
+                .unwrap()
+                .value
+            ).unwrap(),
+            Uint128::new(19)
+        );
+
+        let contract_balance: cw20::BalanceResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: game_addr.to_string(),
+            }
+        ).unwrap();
+        assert_eq!(contract_balance.balance, Uint128::new(100));
+
+        let winner_balance: cw20::BalanceResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: player2_addr.to_string(),
+        },
+        )
+        .unwrap();
+
+        assert_eq!(winner_balance.balance, Uint128::new(1_000_000 - 1_000 + 1919));
+
+        let token_info: cw20::TokenInfoResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::TokenInfo {},
+            )
+        .unwrap();
+
+        assert_eq!(token_info.total_supply, Uint128::new(2_000_000 + 19));
+
+        let err = app
+            .execute_contract(
+                "player1".into_addr(),
+                game_addr.clone(),
+                &ExecuteMsg::Play {
+                    game_id: 1,
+                    field: (1, 0),
+                    value: false,
+                    salt: "".to_owned(),
+                    proof: Proof::Hex(vec![])
+                },
+                &[]
+            )
+            .unwrap_err();
+
+        assert_eq!(ContractError::GameFinished {  }, err.downcast().unwrap())
+
+    }
+
+    #[test]
+    fn concurrent_games_are_isolated() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        let game_id = create_and_join_game(&mut app, &game_addr, vec![1]);
+        assert_eq!(game_id, 2);
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 2);
+
+        app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &player2_board().play(2, (1, 0)),
+                &[]
+            )
+            .unwrap();
+
+        let response: GameStateResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 2 })
+            .unwrap();
+        assert!(response.started);
+        assert_eq!(response.turn, player2_addr);
+
+        let response: GameStateResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 1 })
+            .unwrap();
+        assert!(!response.started);
+        assert_eq!(response.turn, player1_addr);
+
+        let err = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &player2_board().play(1, (1, 0)),
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::GameNotStarted {  }, err.downcast().unwrap());
+    }
+
+    #[test]
+    fn lobby() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        // game 1 was joined by init_app, open three more
+        for _ in 0..3 {
+            app.execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &mock_create_game_msg(vec![1]),
+                &[]
+            ).unwrap();
+        }
+
+        let response: OpenGamesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                game_addr.clone(),
+                &QueryMsg::GetOpenGames { start_after: None, limit: Some(2) }
+            )
+            .unwrap();
+        assert_eq!(
+            response.games.iter().map(|game| game.game_id).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(response.games[0].creator, player1_addr);
+        assert_eq!(response.games[0].stake, Uint128::new(1000));
+        assert_eq!(response.games[0].turn_duration, 60);
+
+        let response: OpenGamesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                game_addr.clone(),
+                &QueryMsg::GetOpenGames { start_after: Some(3), limit: None }
+            )
+            .unwrap();
+        assert_eq!(
+            response.games.iter().map(|game| game.game_id).collect::<Vec<_>>(),
+            vec![4]
+        );
+
+        // game cannot start before an opponent joins
+        send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player1_addr,
+            1000,
+            &ReceiveMsg::Deposit { game_id: 3 }
+        ).unwrap();
+
+        let response: GameStateResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 3 })
+            .unwrap();
+        assert!(!response.started);
+
+        // joining with the stake attached starts the game right away
+        let response = send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player2_addr,
+            1000,
+            &ReceiveMsg::JoinGame { game_id: 3, board: player2_board().root() }
+        ).unwrap();
+        assert!(response.events.iter().any(|ev| ev.ty == "wasm-game_started"));
+
+        let err = app
+            .execute_contract(
+                "attacker".into_addr(),
+                game_addr.clone(),
+                &mock_join_game_msg(3),
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::GameNotOpen {  }, err.downcast().unwrap());
+
+        let response: OpenGamesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                game_addr.clone(),
+                &QueryMsg::GetOpenGames { start_after: None, limit: None }
+            )
+            .unwrap();
+        assert_eq!(
+            response.games.iter().map(|game| game.game_id).collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+
+        let response: PlayersResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetPlayers { game_id: 3 })
+            .unwrap();
+        assert_eq!(response.players.len(), 2);
+        assert_eq!(response.players[0].address, player2_addr);
+        assert_eq!(response.players[0].stake, Uint128::new(1000));
+        assert_eq!(response.players[0].board_root, player2_board().root());
+    }
+
+    #[test]
+    fn only_invitee_can_join() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (_, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::CreateGame {
+                stake: Uint128::new(1000),
+                fleet: vec![1],
+                width: 3,
+                height: 3,
+                turn_duration: Some(120),
+                board: player1_board().root(),
+                invitee: Some(player2_addr.to_string()),
+                min_rating: None,
+                max_rating: None,
+                token_address: None,
+                denom: None,
+                proof_format: None,
+                salvo: None,
+                extra_turn: None,
+                weapons: None,
+            },
+            &[]
+        ).unwrap();
+
+        let err = app
+            .execute_contract(
+                "attacker".into_addr(),
+                game_addr.clone(),
+                &mock_join_game_msg(2),
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {  }, err.downcast().unwrap());
+
+        let err = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &mock_join_game_msg(2),
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidPlayers {  }, err.downcast().unwrap());
+
+        app.execute_contract(
+            player2_addr.clone(),
+            game_addr.clone(),
+            &mock_join_game_msg(2),
+            &[]
+        ).unwrap();
+
+        let response: GameConfigResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameConfig { game_id: 2 })
+            .unwrap();
+        assert_eq!(response.invitee, Some(player2_addr));
+        assert_eq!(response.turn_duration, 120);
+    }
+
+    #[test]
+    fn withdraw_before_opponent_deposits() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player1_addr,
+            1000,
+            &ReceiveMsg::Deposit { game_id: 1 }
+        ).unwrap();
+
+        let response = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Withdraw { game_id: 1 },
+                &[]
+            )
+            .unwrap();
+
+        let wasm = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap();
+        assert_eq!(
+            wasm.attributes
+                .iter()
+                .find(|attr| attr.key == "action")
+                .unwrap()
+                .value,
+            "withdraw"
+        );
+
+        let player1_balance: BalanceResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: player1_addr.to_string(),
+                },
+        ).unwrap();
+        assert_eq!(player1_balance.balance, Uint128::new(1_000_000));
+
+        let err = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Withdraw { game_id: 1 },
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::NoDeposit {  }, err.downcast().unwrap());
+
+        // the opponent's deposit alone does not start the game
+        send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player2_addr,
+            1000,
+            &ReceiveMsg::Deposit { game_id: 1 }
+        ).unwrap();
+
+        let response: GameStateResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 1 })
+            .unwrap();
+        assert!(!response.started);
+    }
+
+    #[test]
+    fn should_reject_invalid_deposits() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        let err = send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player1_addr,
+            999,
+            &ReceiveMsg::Deposit { game_id: 1 }
+        ).unwrap_err();
+        assert_eq!(ContractError::InvalidStake {  }, err.downcast().unwrap());
+
+        send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player1_addr,
+            1000,
+            &ReceiveMsg::Deposit { game_id: 1 }
+        ).unwrap();
+
+        let err = send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player1_addr,
+            1000,
+            &ReceiveMsg::Deposit { game_id: 1 }
+        ).unwrap_err();
+        assert_eq!(ContractError::AlreadyDeposited {  }, err.downcast().unwrap());
+
+        // hooks are only accepted from the game's token contract
+        let err = app
+            .execute_contract(
+                "attacker".into_addr(),
+                game_addr.clone(),
+                &ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+                    sender: player2_addr.to_string(),
+                    amount: Uint128::new(1000),
+                    msg: to_json_binary(&ReceiveMsg::Deposit { game_id: 1 }).unwrap(),
+                }),
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidToken {  }, err.downcast().unwrap());
+    }
+
+    #[test]
+    fn should_reject_proof_for_another_field() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        // valid proof of the ship at (0, 2), replayed against every other field
+        let board = player2_board();
+        for field in [(0, 0), (0, 1), (1, 2), (2, 2), (2, 0)] {
+            let err = app
+                .execute_contract(
+                    player1_addr.clone(),
+                    game_addr.clone(),
+                    &ExecuteMsg::Play {
+                        game_id: 1,
+                        field,
+                        value: true,
+                        salt: board.salt((0, 2)),
+                        proof: board.proof((0, 2))
+                    },
+                    &[]
+                )
+                .unwrap_err();
+
+            assert_eq!(ContractError::InvalidProof {  }, err.downcast().unwrap());
+        }
+
+        // the same proof is accepted only at the committed field
+        app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &board.play(1, (0, 2)),
+                &[]
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn should_reject_proof_with_flipped_value() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        let board = player2_board();
+        let err = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Play {
+                    game_id: 1,
+                    field: (1, 0),
+                    value: true,
+                    salt: board.salt((1, 0)),
+                    proof: board.proof((1, 0))
+                },
+                &[]
+            )
+            .unwrap_err();
+
+        assert_eq!(ContractError::InvalidProof {  }, err.downcast().unwrap());
+
+        // a salt belonging to another field does not open this one
+        let err = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Play {
+                    game_id: 1,
+                    field: (1, 0),
+                    value: false,
+                    salt: board.salt((1, 1)),
+                    proof: board.proof((1, 0))
+                },
+                &[]
+            )
+            .unwrap_err();
+
+        assert_eq!(ContractError::InvalidProof {  }, err.downcast().unwrap());
+    }
+
+    #[test]
+    fn should_throw_invalid_salt_error() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        let err = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Play {
+                    game_id: 1,
+                    field: (1, 0),
+                    value: false,
+                    salt: "1234".to_owned(),
+                    proof: player2_board().proof((1, 0))
+                },
+                &[]
+            )
+            .unwrap_err();
+
+        assert_eq!(ContractError::InvalidSalt {  }, err.downcast().unwrap());
+    }
+
+    #[test]
+    fn should_throw_wrong_turn_error() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        let err = app
+            .execute_contract(
+                "player2".into_addr(),
+                game_addr.clone(),
+                &ExecuteMsg::Play {
+                    game_id: 1,
+                    field: (1, 0),
+                    value: false,
+                    salt: "".to_owned(),
+                    proof: Proof::Hex(vec![])
+                },
+                &[]
+            )
+            .unwrap_err();
+
+        assert_eq!(ContractError::WrongTurn {  }, err.downcast().unwrap())
+    }
+
+    #[test]
+    fn should_throw_game_started_error() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        let err = send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player1_addr,
+            1000,
+            &ReceiveMsg::Deposit { game_id: 1 }
+        ).unwrap_err();
+        assert_eq!(ContractError::GameStarted {  }, err.downcast().unwrap());
+
+        let err = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Withdraw { game_id: 1 },
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::GameStarted {  }, err.downcast().unwrap())
+    }
+
+    #[test]
+    fn should_throw_turn_expired_error() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(
+            player1_addr.clone(),
+            player2_addr.clone()
+        );
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        app.update_block(|b| b.time = b.time.plus_seconds(1000));
+
+        let err = app
+            .execute_contract(
+                "player1".into_addr(),
+                game_addr.clone(),
+                &ExecuteMsg::Play {
+                    game_id: 1,
+                    field: (1, 0),
+                    value: false,
+                    salt: "".to_owned(),
+                    proof: Proof::Hex(vec![])
+                },
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::TurnExpired {  }, err.downcast().unwrap());
+    }
+
+    #[test]
+    fn should_throw_unauthorized_error() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (_, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        let err = app
+            .execute_contract(
+                "attacker".into_addr(), 
+                game_addr.clone(), 
+                &ExecuteMsg::Withdraw { game_id: 1 }, 
+                &[]
+        ).unwrap_err();
+
+        assert_eq!(ContractError::Unauthorized {  }, err.downcast().unwrap())
+    }
+
+    #[test]
+    fn should_throw_game_not_started_error() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (_, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        let err = app
+            .execute_contract(
+                "player2".into_addr(),
+                game_addr.clone(),
+                &ExecuteMsg::Play {
+                    game_id: 1,
+                    field: (1, 0),
+                    value: false,
+                    salt: "".to_owned(),
+                    proof: Proof::Hex(vec![])
+                },
+                &[]
+            )
+            .unwrap_err();
+
+        assert_eq!(ContractError::GameNotStarted {  }, err.downcast().unwrap())
+    }
+
+    #[test]
+    fn timeout_win() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(
+            player1_addr.clone(),
+            player2_addr.clone()
+        );
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        app.update_block(|b| b.time = b.time.plus_seconds(1000));
+
+        let response = app.execute_contract(
+            player2_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::TimeoutWin { game_id: 1 },
+            &[]
+        ).unwrap();
+
+        let wasm = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap();
+        assert_eq!(
+            wasm.attributes
+                .iter()
+                .find(|attr| attr.key == "action")
+                .unwrap()
+                .value,
+            "timeout_check"
+        );
+
+        let wasm = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap();
+        assert_eq!(
+            wasm.attributes
+                .iter()
+                .find(|attr| attr.key == "winner")
+                .unwrap()
+                .value,
+            player2_addr.clone().to_string()
+        );
+
+        let game_won: Vec<_> = response
+            .events
+            .iter()
+            .filter(|ev| ev.ty == "wasm-game_won")
+            .collect();
+        assert_eq!(
+            game_won[0]
+                .attributes
+                .iter()
+                .find(|attr| attr.key == "sank")
+                .unwrap()
+                .value,
+            stringify!((-1, -1))
+        );
+
+        // only the winner reveals, the pot is paid once the reveal window closes
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &player2_board().reveal(1), &[])
+            .unwrap();
+
+        let err = app
+            .execute_contract(
+                player2_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Settle { game_id: 1 },
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::RevealNotClosed {  }, err.downcast().unwrap());
+
+        app.update_block(|b| b.time = b.time.plus_seconds(301));
+
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &player1_board().reveal(1), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::RevealClosed {  }, err.downcast().unwrap());
+
+        let response = app
+            .execute_contract(
+                player2_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Settle { game_id: 1 },
+                &[]
+            )
+            .unwrap();
+
+        let wasm = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap();
+        assert!(response.events.iter().any(|ev| ev.ty == "wasm-slashed"));
+
+        assert_eq!(
+            Uint128::from_str(
+                &wasm.attributes
+                .iter()
+                .find(|a| a.key == "payout")
+                .unwrap()
+                .value
+            ).unwrap(),
+            Uint128::new(1900)
+        );
+
+        assert_eq!(
+            Uint128::from_str(
+                &wasm.attributes
+                .iter()
+                .find(|a| a.key == "fee_retained")
+                .unwrap()
+                .value
+            ).unwrap(),
+            Uint128::new(100)
+        );
+
+        assert_eq!(
+            Uint128::from_str(
+                &wasm.attributes
+                .iter()
+                .find(|a| a.key == "minted_reward")
+                .unwrap()
+                .value
+            ).unwrap(),
+            Uint128::new(19)
+        );
+
+        let contract_balance: cw20::BalanceResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: game_addr.to_string(),
+            }
+        ).unwrap();
+        assert_eq!(contract_balance.balance, Uint128::new(100));
+
+        let winner_balance: cw20::BalanceResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::Balance {
+                    address: player2_addr.to_string(),
+        },
+        )
+        .unwrap();
+
+        assert_eq!(winner_balance.balance, Uint128::new(1_000_000 - 1_000 + 1919));
+
+        let token_info: cw20::TokenInfoResponse = app.wrap()
+            .query_wasm_smart(
+                cw20_addr.clone(),
+                &Cw20QueryMsg::TokenInfo {},
+            )
+        .unwrap();
+
+        assert_eq!(token_info.total_supply, Uint128::new(2_000_000 + 19));
+
+        let err = app
+            .execute_contract(
+                "player1".into_addr(),
+                game_addr.clone(),
+                &ExecuteMsg::Play {
+                    game_id: 1,
+                    field: (1, 0),
                     value: true,
-                    proof: vec![
-                        ProofStep {
-                            hash: "fcbcf165908dd18a9e49f7ff27810176db8e9f63b4352213741664245224f8aa".to_owned(),
-                            is_left: false
-                        },
-                        ProofStep {
-                            hash: "33873cc7849b5a36e72508f177d655726b17aa5adeded878804cc402ae1ecbc1".to_owned(),
-                            is_left: false
-                        },
-                        ProofStep {
-                            hash: "9a74c4cb6669420048ea661a3b8e501ca5c10c2f2680a41acc2128422c1ff6b6".to_owned(),
-                            is_left: true
-                        },
-                        ProofStep {
-                            hash: "2e176322075537fd763b4405a4392854d0f67b079f814bbe9d9683ca371343e9".to_owned(),
-                            is_left: false
-                        }
-                    ]
+                    salt: "".to_owned(),
+                    proof: Proof::Hex(vec![])
                 },
                 &[]
             )
+            .unwrap_err();
+
+        assert_eq!(ContractError::GameFinished {  }, err.downcast().unwrap())
+
+    }
+
+    #[test]
+    fn fire_and_answer() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(
+            player1_addr.clone(),
+            player2_addr.clone()
+        );
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        let err = app
+            .execute_contract(
+                player2_addr.clone(),
+                game_addr.clone(),
+                &player1_board().answer(1, (0, 0)),
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::WrongTurn {  }, err.downcast().unwrap());
+
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::Fire { game_id: 1, field: (0, 0) },
+            &[]
+        ).unwrap();
+
+        let err = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Fire { game_id: 1, field: (0, 1) },
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::WrongTurn {  }, err.downcast().unwrap());
+
+        let err = app
+            .execute_contract(
+                player2_addr.clone(),
+                game_addr.clone(),
+                &player1_board().play(1, (1, 1)),
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::ShotPending {  }, err.downcast().unwrap());
+
+        // the proof has to be for the field that was fired at
+        let err = app
+            .execute_contract(
+                player2_addr.clone(),
+                game_addr.clone(),
+                &player2_board().answer(1, (0, 2)),
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::InvalidProof {  }, err.downcast().unwrap());
+
+        let response = app
+            .execute_contract(
+                player2_addr.clone(),
+                game_addr.clone(),
+                &player2_board().answer(1, (0, 0)),
+                &[]
+            )
+            .unwrap();
+        assert!(response.events.iter().any(|ev| ev.ty == "wasm-ship_missed"));
+
+        // player2 fires back and player1 has to answer a hit on its only ship
+        app.execute_contract(
+            player2_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::Fire { game_id: 1, field: (1, 1) },
+            &[]
+        ).unwrap();
+
+        let response = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &player1_board().answer(1, (1, 1)),
+                &[]
+            )
             .unwrap();
 
         let wasm = response
@@ -372,415 +2594,1372 @@ pub mod tests {
         assert_eq!(
             wasm.attributes
                 .iter()
-                .find(|attr| attr.key == "action")
+                .find(|attr| attr.key == "winner")
                 .unwrap()
                 .value,
-            "play"
+            player2_addr.to_string()
+        );
+
+        let state: GameStateResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr, &QueryMsg::GetGameState { game_id: 1 })
+            .unwrap();
+        assert!(state.finished);
+        assert_eq!(state.pending_shot, None);
+    }
+
+    #[test]
+    fn unanswered_shot_forfeits() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(
+            player1_addr.clone(),
+            player2_addr.clone()
+        );
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::Fire { game_id: 1, field: (0, 2) },
+            &[]
+        ).unwrap();
+
+        app.update_block(|b| b.time = b.time.plus_seconds(61));
+
+        let err = app
+            .execute_contract(
+                player2_addr.clone(),
+                game_addr.clone(),
+                &player2_board().answer(1, (0, 2)),
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::TurnExpired {  }, err.downcast().unwrap());
+
+        let response = app
+            .execute_contract(
+                player1_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::TimeoutWin { game_id: 1 },
+                &[]
+            )
+            .unwrap();
+
+        let wasm = response
+            .events.iter()
+            .find(|ev| ev.ty == "wasm")
+            .unwrap();
+        assert_eq!(
+            wasm.attributes
+                .iter()
+                .find(|attr| attr.key == "winner")
+                .unwrap()
+                .value,
+            player1_addr.to_string()
+        );
+    }
+
+    pub fn balance(app: &App, cw20_addr: &Addr, address: &Addr) -> Uint128 {
+        let response: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(cw20_addr.clone(), &Cw20QueryMsg::Balance { address: address.to_string() })
+            .unwrap();
+        response.balance
+    }
+
+    #[test]
+    fn resign() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(
+            player1_addr.clone(),
+            player2_addr.clone()
+        );
+
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::Resign { game_id: 1 }, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::GameNotStarted {  }, err.downcast().unwrap());
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        let err = app
+            .execute_contract("attacker".into_addr(), game_addr.clone(), &ExecuteMsg::Resign { game_id: 1 }, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {  }, err.downcast().unwrap());
+
+        let response = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::Resign { game_id: 1 }, &[])
+            .unwrap();
+        assert!(response.events.iter().any(|ev| ev.ty == "wasm-resigned"));
+
+        let state: GameStateResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 1 })
+            .unwrap();
+        assert!(state.finished);
+        assert_eq!(state.winner, Some(player2_addr.clone()));
+
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &player1_board().play(1, (0, 2)), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::GameFinished {  }, err.downcast().unwrap());
+
+        // the winner still has to reveal a legal board to be paid
+        for (player, board) in [(&player1_addr, player1_board()), (&player2_addr, player2_board())] {
+            app.execute_contract(player.clone(), game_addr.clone(), &board.reveal(1), &[])
+                .unwrap();
+        }
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::Settle { game_id: 1 }, &[])
+            .unwrap();
+
+        assert_eq!(balance(&app, &cw20_addr, &player2_addr), Uint128::new(1_000_000 - 1_000 + 1919));
+    }
+
+    #[test]
+    fn draw() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(
+            player1_addr.clone(),
+            player2_addr.clone()
+        );
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        let err = app
+            .execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::AcceptDraw { game_id: 1 }, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::NoDrawOffer {  }, err.downcast().unwrap());
+
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::OfferDraw { game_id: 1 }, &[])
+            .unwrap();
+
+        // a player cannot accept their own offer
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::AcceptDraw { game_id: 1 }, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::NoDrawOffer {  }, err.downcast().unwrap());
+
+        let response = app
+            .execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::AcceptDraw { game_id: 1 }, &[])
+            .unwrap();
+        assert!(response.events.iter().any(|ev| ev.ty == "wasm-draw"));
+
+        for player in [&player1_addr, &player2_addr] {
+            assert_eq!(balance(&app, &cw20_addr, player), Uint128::new(1_000_000 - 50));
+        }
+        assert_eq!(balance(&app, &cw20_addr, &game_addr), Uint128::new(100));
+
+        let response: AccruedFeesResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetAccruedFees {})
+            .unwrap();
+        assert_eq!(response.fees[0].amount, Uint128::new(100));
+
+        let err = app
+            .execute_contract(player2_addr, game_addr, &ExecuteMsg::Settle { game_id: 1 }, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::AlreadySettled {  }, err.downcast().unwrap());
+    }
+
+    #[test]
+    fn cancel() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(
+            player1_addr.clone(),
+            player2_addr.clone()
+        );
+
+        send_stake(&mut app, &cw20_addr, &game_addr, &player1_addr, 1000, &ReceiveMsg::Deposit { game_id: 1 })
+            .unwrap();
+
+        let err = app
+            .execute_contract("attacker".into_addr(), game_addr.clone(), &ExecuteMsg::Cancel { game_id: 1 }, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {  }, err.downcast().unwrap());
+
+        let response = app
+            .execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::Cancel { game_id: 1 }, &[])
+            .unwrap();
+        assert!(response.events.iter().any(|ev| ev.ty == "wasm-game_cancelled"));
+        assert_eq!(response.events.iter().filter(|ev| ev.ty == "wasm-refund").count(), 1);
+
+        assert_eq!(balance(&app, &cw20_addr, &player1_addr), Uint128::new(1_000_000));
+        assert_eq!(balance(&app, &cw20_addr, &game_addr), Uint128::zero());
+
+        let err = send_stake(&mut app, &cw20_addr, &game_addr, &player2_addr, 1000, &ReceiveMsg::Deposit { game_id: 1 })
+            .unwrap_err();
+        assert_eq!(ContractError::GameFinished {  }, err.downcast().unwrap());
+
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::Cancel { game_id: 1 }, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::GameFinished {  }, err.downcast().unwrap());
+
+        // an open game leaves the lobby, a started one cannot be cancelled
+        let response = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &mock_create_game_msg(vec![1]), &[])
+            .unwrap();
+        let game_id: u64 = response.events.iter()
+            .flat_map(|ev| ev.attributes.iter())
+            .find(|attr| attr.key == "game_id")
+            .unwrap()
+            .value
+            .parse()
+            .unwrap();
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::Cancel { game_id }, &[])
+            .unwrap();
+        let response: OpenGamesResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetOpenGames { start_after: None, limit: None })
+            .unwrap();
+        assert!(response.games.is_empty());
+
+        let game_id = create_and_join_game(&mut app, &game_addr, vec![1]);
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, game_id);
+        let err = app
+            .execute_contract(player1_addr, game_addr, &ExecuteMsg::Cancel { game_id }, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::GameStarted {  }, err.downcast().unwrap());
+    }
+
+    #[test]
+    fn move_history() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(
+            player1_addr.clone(),
+            player2_addr.clone()
+        );
+
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+
+        for (player, board, field) in [
+            (&player1_addr, player2_board(), (0, 0)),
+            (&player2_addr, player1_board(), (0, 0)),
+            (&player1_addr, player2_board(), (1, 0)),
+        ] {
+            app.execute_contract(player.clone(), game_addr.clone(), &board.play(1, field), &[])
+                .unwrap();
+        }
+
+        // a miss cannot be repeated, neither directly nor with Fire
+        let err = app
+            .execute_contract(player2_addr.clone(), game_addr.clone(), &player1_board().play(1, (0, 0)), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::AlreadyFired {  }, err.downcast().unwrap());
+
+        let err = app
+            .execute_contract(
+                player2_addr.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::Fire { game_id: 1, field: (0, 0) },
+                &[]
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::AlreadyFired {  }, err.downcast().unwrap());
+
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &player1_board().play(1, (1, 1)), &[])
+            .unwrap();
+
+        let response: MovesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                game_addr.clone(),
+                &QueryMsg::GetMoves { game_id: 1, start_after: None, limit: Some(2) }
+            )
+            .unwrap();
+        assert_eq!(response.moves.len(), 2);
+        assert_eq!(response.moves[0].index, 0);
+        assert_eq!(response.moves[0].shooter, player1_addr);
+        assert_eq!(response.moves[0].field, (0, 0));
+        assert!(!response.moves[0].hit);
+        assert_eq!(response.moves[0].time, app.block_info().time.seconds());
+        assert_eq!(response.moves[1].shooter, player2_addr);
+
+        let response: MovesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                game_addr,
+                &QueryMsg::GetMoves { game_id: 1, start_after: Some(1), limit: None }
+            )
+            .unwrap();
+        assert_eq!(response.moves.len(), 2);
+        assert_eq!(response.moves[0].index, 2);
+        assert_eq!(response.moves[1].shooter, player2_addr);
+        assert_eq!(response.moves[1].field, (1, 1));
+        assert!(response.moves[1].hit);
+    }
+
+    #[test]
+    fn board_view() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        let player1_board = MockBoard::new(
+            vec![
+                vec![false, false, false],
+                vec![true, true, false],
+                vec![false, false, true],
+            ],
+            "player1",
+        );
+        let player2_board = MockBoard::new(
+            vec![
+                vec![true, true, false],
+                vec![false, false, false],
+                vec![false, false, true],
+            ],
+            "player2",
         );
 
-        let wasm = response
-            .events.iter()
-            .find(|ev| ev.ty == "wasm")
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::CreateGame {
+                stake: Uint128::new(1000),
+                fleet: vec![2, 1],
+                width: 3,
+                height: 3,
+                turn_duration: None,
+                board: player1_board.root(),
+                invitee: None,
+                min_rating: None,
+                max_rating: None,
+                token_address: None,
+                denom: None,
+                proof_format: None,
+                salvo: None,
+                extra_turn: None,
+                weapons: None,
+            },
+            &[]
+        ).unwrap();
+        send_stake(&mut app, &cw20_addr, &game_addr, &player1_addr, 1000, &ReceiveMsg::Deposit { game_id: 2 })
             .unwrap();
-        assert_eq!(
-            wasm.attributes
-                .iter()
-                .find(|attr| attr.key == "winner")
-                .unwrap()
-                .value,
-            player2_addr.clone().to_string()
-        );
+        send_stake(
+            &mut app,
+            &cw20_addr,
+            &game_addr,
+            &player2_addr,
+            1000,
+            &ReceiveMsg::JoinGame { game_id: 2, board: player2_board.root() }
+        ).unwrap();
 
-        assert_eq!(
-            Uint128::from_str(
-                &wasm.attributes
-                .iter()
-                .find(|a| a.key == "payout")
-                .unwrap()
-                .value
-            ).unwrap(),
-            Uint128::new(1900)
-        );
+        for (player, board, field) in [
+            (&player1_addr, &player2_board, (0, 0)),
+            (&player2_addr, &player1_board, (0, 0)),
+            (&player1_addr, &player2_board, (2, 2)),
+            (&player2_addr, &player1_board, (0, 1)),
+            (&player1_addr, &player2_board, (1, 2)),
+            (&player2_addr, &player1_board, (0, 2)),
+            (&player1_addr, &player2_board, (2, 1)),
+        ] {
+            app.execute_contract(player.clone(), game_addr.clone(), &board.play(2, field), &[])
+                .unwrap();
+        }
 
-        assert_eq!(
-            Uint128::from_str(
-                &wasm.attributes
-                .iter()
-                .find(|a| a.key == "fee_retained")
+        let board_view = |app: &App, player: &Addr| -> BoardViewResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    game_addr.clone(),
+                    &QueryMsg::GetBoardView { game_id: 2, player: player.to_string() }
+                )
                 .unwrap()
-                .value
-            ).unwrap(),
-            Uint128::new(100)
-        );
+        };
 
-        assert_eq!(
-            Uint128::from_str(
-                &wasm.attributes
-                .iter()
-                .find(|a| a.key == "minted_reward")
-                .unwrap()
-                .value
-            ).unwrap(),
-            Uint128::new(19)
-        );
+        // only players of the game have a view of the opponent's board
+        app.wrap()
+            .query_wasm_smart::<BoardViewResponse>(
+                game_addr.clone(),
+                &QueryMsg::GetBoardView { game_id: 2, player: "spectator".into_addr().to_string() }
+            )
+            .unwrap_err();
 
-        let game_won: Vec<_> = response
-            .events
-            .iter()
-            .filter(|ev| ev.ty == "wasm-game_won")
-            .collect();
+        // the single hit at (2, 2) is surrounded by misses, the one at (0, 0) is not
+        let response = board_view(&app, &player1_addr);
+        assert_eq!((response.width, response.height), (3, 3));
         assert_eq!(
-            game_won[0]
-                .attributes
-                .iter()
-                .find(|attr| attr.key == "sank")
-                .unwrap()
-                .value,
-            stringify!((1, 1))
+            response.cells,
+            vec![
+                vec![Cell::Hit, Cell::Unknown, Cell::Unknown],
+                vec![Cell::Unknown, Cell::Unknown, Cell::Miss],
+                vec![Cell::Unknown, Cell::Miss, Cell::Sunk],
+            ]
         );
+        assert_eq!(response.remaining_ships, 1);
 
-        let contract_balance: cw20::BalanceResponse = app.wrap()
-            .query_wasm_smart(
-                cw20_addr.clone(),
-                &Cw20QueryMsg::Balance {
-                    address: game_addr.to_string(),
-            }
-        ).unwrap();
-        assert_eq!(contract_balance.balance, Uint128::new(100));
+        let response = board_view(&app, &player2_addr);
+        assert_eq!(response.cells[0], vec![Cell::Miss, Cell::Miss, Cell::Miss]);
+        assert_eq!(response.remaining_ships, 2);
 
-        let winner_balance: cw20::BalanceResponse = app.wrap()
-            .query_wasm_smart(
-                cw20_addr.clone(),
-                &Cw20QueryMsg::Balance {
-                    address: player2_addr.to_string(),
-        },
-        )
-        .unwrap();
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &player1_board.play(2, (1, 2)), &[])
+            .unwrap();
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &player2_board.play(2, (0, 1)), &[])
+            .unwrap();
 
-        assert_eq!(winner_balance.balance, Uint128::new(1_000_000 - 1_000 + 1919));
+        // the last ship is only known to be sunk once the board is revealed
+        let response = board_view(&app, &player1_addr);
+        assert_eq!(response.cells[0], vec![Cell::Hit, Cell::Hit, Cell::Unknown]);
+        assert_eq!(response.remaining_ships, 1);
 
-        let token_info: cw20::TokenInfoResponse = app.wrap()
-            .query_wasm_smart(
-                cw20_addr.clone(),
-                &Cw20QueryMsg::TokenInfo {},
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &player2_board.reveal(2), &[])
+            .unwrap();
+
+        let response = board_view(&app, &player1_addr);
+        assert_eq!(response.cells[0], vec![Cell::Sunk, Cell::Sunk, Cell::Unknown]);
+        assert_eq!(response.remaining_ships, 0);
+    }
+
+    #[test]
+    fn migration() {
+        let mut app = App::default();
+        let code = ContractWrapper::new(execute, instantiate, query).with_migrate(migrate);
+        let code_id = app.store_code(Box::new(code));
+
+        let game_addr = app
+            .instantiate_contract(
+                code_id,
+                "owner".into_addr(),
+                &mock_instantiate_msg("token".into_addr()),
+                &[],
+                "Contract",
+                Some("owner".into_addr().to_string())
             )
-        .unwrap();
+            .unwrap();
 
-        assert_eq!(token_info.total_supply, Uint128::new(2_000_000 + 19));
+        let version = cw2::query_contract_info(&app.wrap(), game_addr.to_string()).unwrap();
+        assert_eq!(version.contract, "crates.io:battleship-game");
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+        // a game stored by 0.1.0, before draws, the move log and native stakes existed
+        #[cosmwasm_schema::cw_serde]
+        struct LegacyConfig {
+            token_address: Addr,
+            creator: Addr,
+            invitee: Option<Addr>,
+            stake: Uint128,
+            fleet: Vec<usize>,
+            width: usize,
+            height: usize,
+            turn_duration: u64,
+            fee_percentage: u64,
+            reward_percentage: u64,
+        }
+        #[cosmwasm_schema::cw_serde]
+        struct LegacyState {
+            started: bool,
+            finished: bool,
+            turn: Addr,
+            last_turn_time: u64,
+        }
+        #[cosmwasm_schema::cw_serde]
+        struct LegacyGame {
+            config: LegacyConfig,
+            state: LegacyState,
+        }
+        let legacy_game = LegacyGame {
+            config: LegacyConfig {
+                token_address: "token".into_addr(),
+                creator: "player1".into_addr(),
+                invitee: None,
+                stake: Uint128::new(1000),
+                fleet: vec![1],
+                width: 3,
+                height: 3,
+                turn_duration: 60,
+                fee_percentage: 5,
+                reward_percentage: 1,
+            },
+            state: LegacyState {
+                started: true,
+                finished: false,
+                turn: "player2".into_addr(),
+                last_turn_time: 10,
+            },
+        };
+        {
+            let mut storage = app.contract_storage_mut(&game_addr);
+            for game_id in 7..=9 {
+                cw_storage_plus::Map::<u64, LegacyGame>::new("games")
+                    .save(storage.as_mut(), game_id, &legacy_game)
+                    .unwrap();
+            }
+            cw2::set_contract_version(storage.as_mut(), "crates.io:battleship-game", "0.1.0").unwrap();
+        }
 
-        let err = app
-            .execute_contract(
-                "player1".into_addr(),
+        // more games than fit in one batch, the migration reports where to continue
+        let response = app
+            .migrate_contract(
+                "owner".into_addr(),
                 game_addr.clone(),
-                &ExecuteMsg::Play {
-                    field: (1, 0),
-                    value: false,
-                    proof: vec![]
-                },
-                &[]
+                &MigrateMsg { start_after: None, limit: Some(2) },
+                code_id
             )
+            .unwrap();
+        let wasm = response.events.iter().find(|ev| ev.ty == "wasm").unwrap();
+        assert!(wasm.attributes.iter().any(|attr| attr.key == "from_version" && attr.value == "0.1.0"));
+        assert!(wasm.attributes.iter().any(|attr| attr.key == "migrated_games" && attr.value == "2"));
+        assert!(wasm.attributes.iter().any(|attr| attr.key == "last_game_id" && attr.value == "8"));
+
+        app.wrap()
+            .query_wasm_smart::<GameStateResponse>(game_addr.clone(), &QueryMsg::GetGameState { game_id: 9 })
             .unwrap_err();
 
-        assert_eq!(ContractError::GameFinished {  }, err.downcast().unwrap())
+        let response = app
+            .migrate_contract(
+                "owner".into_addr(),
+                game_addr.clone(),
+                &MigrateMsg { start_after: Some(8), limit: Some(2) },
+                code_id
+            )
+            .unwrap();
+        let wasm = response.events.iter().find(|ev| ev.ty == "wasm").unwrap();
+        assert!(wasm.attributes.iter().any(|attr| attr.key == "migrated_games" && attr.value == "1"));
+        assert!(!wasm.attributes.iter().any(|attr| attr.key == "last_game_id"));
+        app.wrap()
+            .query_wasm_smart::<GameStateResponse>(game_addr.clone(), &QueryMsg::GetGameState { game_id: 9 })
+            .unwrap();
+
+        let state: GameStateResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 7 })
+            .unwrap();
+        assert_eq!(state.turn, "player2".into_addr());
+        assert_eq!(state.moves, 0);
+        assert_eq!(state.pending_shot, None);
+        assert!(!state.settled);
+
+        let config: GameConfigResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameConfig { game_id: 7 })
+            .unwrap();
+        assert_eq!(config.asset, StakeAsset::Cw20 { address: "token".into_addr() });
 
+        let version = cw2::query_contract_info(&app.wrap(), game_addr.to_string()).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+        // downgrades and migrations from other contracts are rejected
+        cw2::set_contract_version(
+            app.contract_storage_mut(&game_addr).as_mut(),
+            "crates.io:battleship-game",
+            "99.0.0"
+        ).unwrap();
+        app.migrate_contract("owner".into_addr(), game_addr.clone(), &MigrateMsg { start_after: None, limit: None }, code_id)
+            .unwrap_err();
+
+        cw2::set_contract_version(
+            app.contract_storage_mut(&game_addr).as_mut(),
+            "crates.io:cw20-base",
+            "0.0.1"
+        ).unwrap();
+        app.migrate_contract("owner".into_addr(), game_addr, &MigrateMsg { start_after: None, limit: None }, code_id)
+            .unwrap_err();
     }
 
     #[test]
-    fn should_throw_wrong_turn_error() {
+    fn leaderboard() {
         let player1_addr = "player1".into_addr();
         let player2_addr = "player2".into_addr();
-        let (_, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
 
-        app
-            .execute_contract(
-                player1_addr.clone(), 
-                game_addr.clone(), 
-                &ExecuteMsg::StartGame {}, 
+        // player2 wins the first game on timeout, the second one is drawn after a miss
+        settle_by_timeout(&mut app, &cw20_addr, &game_addr, 1);
+
+        let game_id = create_and_join_game(&mut app, &game_addr, vec![1]);
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, game_id);
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &player2_board().play(game_id, (0, 0)), &[])
+            .unwrap();
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::OfferDraw { game_id }, &[])
+            .unwrap();
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::AcceptDraw { game_id }, &[])
+            .unwrap();
+
+        let stats: PlayerStatsResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetPlayerStats { address: player1_addr.to_string() })
+            .unwrap();
+        assert_eq!(stats.games_played, 2);
+        assert_eq!((stats.wins, stats.losses, stats.draws, stats.timeouts), (0, 1, 1, 1));
+        assert_eq!((stats.shots_fired, stats.hits), (1, 0));
+        assert_eq!(stats.accuracy, Decimal::zero());
+        assert_eq!(stats.net_won, Int128::new(-1000 - 50));
+
+        let stats: PlayerStatsResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetPlayerStats { address: player2_addr.to_string() })
+            .unwrap();
+        assert_eq!((stats.wins, stats.losses, stats.draws, stats.timeouts), (1, 0, 1, 0));
+        assert_eq!(stats.net_won, Int128::new(1919 - 1000 - 50));
+
+        let leaderboard = |app: &App, sort_by, start_after: Option<&Addr>| -> LeaderboardResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    game_addr.clone(),
+                    &QueryMsg::Leaderboard {
+                        sort_by,
+                        start_after: start_after.map(|address| address.to_string()),
+                        limit: Some(1),
+                    }
+                )
+                .unwrap()
+        };
+
+        let response = leaderboard(&app, LeaderboardSort::Wins, None);
+        assert_eq!(response.players.len(), 1);
+        assert_eq!(response.players[0].address, player2_addr);
+
+        let response = leaderboard(&app, LeaderboardSort::Earnings, Some(&player2_addr));
+        assert_eq!(response.players[0].address, player1_addr);
+
+        let response = leaderboard(&app, LeaderboardSort::Earnings, Some(&player1_addr));
+        assert!(response.players.is_empty());
+    }
+
+    #[test]
+    fn ratings() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        let rating = |app: &App, address: &Addr| -> RatingResponse {
+            app.wrap()
+                .query_wasm_smart(game_addr.clone(), &QueryMsg::GetRating { address: address.to_string() })
+                .unwrap()
+        };
+
+        let response = rating(&app, &player1_addr);
+        assert_eq!((response.rating, response.games, response.provisional), (1200, 0, true));
+
+        // equal ratings, the provisional winner gains half of the k-factor of 40
+        settle_by_timeout(&mut app, &cw20_addr, &game_addr, 1);
+        assert_eq!(rating(&app, &player1_addr).rating, 1180);
+        let response = rating(&app, &player2_addr);
+        assert_eq!((response.rating, response.games, response.provisional), (1220, 1, true));
+
+        // the higher rated player loses points in a draw
+        let game_id = create_and_join_game(&mut app, &game_addr, vec![1]);
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, game_id);
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::OfferDraw { game_id }, &[])
+            .unwrap();
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::AcceptDraw { game_id }, &[])
+            .unwrap();
+        assert_eq!(rating(&app, &player1_addr).rating, 1182);
+        assert_eq!(rating(&app, &player2_addr).rating, 1218);
+
+        let create_game = |min_rating, max_rating| ExecuteMsg::CreateGame {
+            stake: Uint128::new(1000),
+            fleet: vec![1],
+            width: 3,
+            height: 3,
+            turn_duration: None,
+            board: player1_board().root(),
+            invitee: None,
+            min_rating,
+            max_rating,
+            token_address: None,
+            denom: None,
+            proof_format: None,
+            salvo: None,
+            extra_turn: None,
+            weapons: None,
+        };
+
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &create_game(Some(1300), Some(1100)), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::RatingOutOfRange {  }, err.downcast().unwrap());
+
+        for (min_rating, max_rating, allowed) in [
+            (Some(1200), None, true),
+            (None, Some(1200), false),
+            (Some(1100), Some(1300), true),
+        ] {
+            app.execute_contract(player1_addr.clone(), game_addr.clone(), &create_game(min_rating, max_rating), &[])
+                .unwrap();
+            let game_id = app
+                .wrap()
+                .query_wasm_smart::<OpenGamesResponse>(
+                    game_addr.clone(),
+                    &QueryMsg::GetOpenGames { start_after: None, limit: None }
+                )
+                .unwrap()
+                .games
+                .last()
+                .unwrap()
+                .game_id;
+
+            let result = app.execute_contract(
+                player2_addr.clone(),
+                game_addr.clone(),
+                &mock_join_game_msg(game_id),
                 &[]
+            );
+            if allowed {
+                result.unwrap();
+            } else {
+                assert_eq!(ContractError::RatingOutOfRange {  }, result.unwrap_err().downcast().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn tournament() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let player3_addr = "player3".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+        app.execute_contract(
+            player1_addr.clone(),
+            cw20_addr.clone(),
+            &Cw20ExecuteMsg::Transfer { recipient: player3_addr.to_string(), amount: Uint128::new(1000) },
+            &[]
+        ).unwrap();
+
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::CreateTournament {
+                entry_fee: Uint128::new(100),
+                max_players: 3,
+                prizes: vec![60, 30],
+                fleet: vec![1],
+                width: 3,
+                height: 3,
+                turn_duration: None,
+            },
+            &[]
         ).unwrap();
 
+        let register = |app: &mut App, player: &Addr, amount| send_stake(
+            app,
+            &cw20_addr,
+            &game_addr,
+            player,
+            amount,
+            &ReceiveMsg::RegisterTournament { tournament_id: 1 }
+        );
+        let err = register(&mut app, &player1_addr, 50).unwrap_err();
+        assert_eq!(ContractError::InvalidStake {  }, err.downcast().unwrap());
+        for player in [&player1_addr, &player2_addr, &player3_addr] {
+            register(&mut app, player, 100).unwrap();
+        }
+        let err = register(&mut app, &player1_addr, 100).unwrap_err();
+        assert_eq!(ContractError::AlreadyRegistered {  }, err.downcast().unwrap());
+
+        let err = app
+            .execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::StartTournament { tournament_id: 1 }, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {  }, err.downcast().unwrap());
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::StartTournament { tournament_id: 1 }, &[])
+            .unwrap();
+
+        // equal ratings keep the registration order, the top seed gets the bye
+        let tournament = |app: &App| -> TournamentResponse {
+            app.wrap()
+                .query_wasm_smart(game_addr.clone(), &QueryMsg::GetTournament { tournament_id: 1 })
+                .unwrap()
+        };
+        let response = tournament(&app);
+        assert_eq!(response.status, TournamentStatusResponse::Running);
+        assert_eq!(response.round, 1);
+        assert_eq!(response.matches[0].players, (Some(player1_addr.clone()), None));
+        assert_eq!(response.matches[0].winner, Some(player1_addr.clone()));
+        assert_eq!(response.matches[1].players, (Some(player2_addr.clone()), Some(player3_addr.clone())));
+        let game_id = response.matches[1].game_id.unwrap();
+
+        let player3_board = MockBoard::new(player2_board().fields, "player3");
         let err = app
             .execute_contract(
-                "player2".into_addr(),
+                player1_addr.clone(),
                 game_addr.clone(),
-                &ExecuteMsg::Play {
-                    field: (1, 0),
-                    value: false,
-                    proof: vec![]
-                },
+                &ExecuteMsg::CommitBoard { game_id, board: player1_board().root() },
                 &[]
             )
             .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {  }, err.downcast().unwrap());
+        for (player, board) in [(&player2_addr, player2_board().root()), (&player3_addr, player3_board.root())] {
+            app.execute_contract(
+                player.clone(),
+                game_addr.clone(),
+                &ExecuteMsg::CommitBoard { game_id, board },
+                &[]
+            ).unwrap();
+        }
+
+        let err = app
+            .execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::OfferDraw { game_id }, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::TournamentGame {  }, err.downcast().unwrap());
+
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::Resign { game_id }, &[])
+            .unwrap();
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &player2_board().reveal(game_id), &[])
+            .unwrap();
+        app.execute_contract(player3_addr.clone(), game_addr.clone(), &player3_board.reveal(game_id), &[])
+            .unwrap();
+        app.execute_contract(player3_addr.clone(), game_addr.clone(), &ExecuteMsg::Settle { game_id }, &[])
+            .unwrap();
+
+        let response = tournament(&app);
+        assert_eq!(response.round, 2);
+        assert_eq!(response.matches[0].players, (Some(player1_addr.clone()), Some(player3_addr.clone())));
+        let game_id = response.matches[0].game_id.unwrap();
+
+        // player3 never commits a board for the final and forfeits it
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::CommitBoard { game_id, board: player1_board().root() },
+            &[]
+        ).unwrap();
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::TimeoutWin { game_id }, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::TurnNotExpired {  }, err.downcast().unwrap());
+        app.update_block(|b| b.time = b.time.plus_seconds(61));
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::TimeoutWin { game_id }, &[])
+            .unwrap();
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &player1_board().reveal(game_id), &[])
+            .unwrap();
+        app.update_block(|b| b.time = b.time.plus_seconds(301));
+
+        let balances = [&player1_addr, &player2_addr, &player3_addr]
+            .map(|player| balance(&app, &cw20_addr, player));
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &ExecuteMsg::Settle { game_id }, &[])
+            .unwrap();
+
+        // 300 in the pool: 60% to the champion, 30% to the finalist, the rest is retained
+        let response = tournament(&app);
+        assert_eq!(response.status, TournamentStatusResponse::Finished);
+        assert_eq!(balance(&app, &cw20_addr, &player1_addr), balances[0] + Uint128::new(180));
+        assert_eq!(balance(&app, &cw20_addr, &player2_addr), balances[1]);
+        assert_eq!(balance(&app, &cw20_addr, &player3_addr), balances[2] + Uint128::new(90));
+
+        let response: AccruedFeesResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetAccruedFees {})
+            .unwrap();
+        assert_eq!(
+            response.fees,
+            vec![AccruedFee { asset: StakeAsset::Cw20 { address: cw20_addr.clone() }, amount: Uint128::new(30) }]
+        );
+    }
+
+    #[test]
+    fn tournament_match_without_boards() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::CreateTournament {
+                entry_fee: Uint128::new(100),
+                max_players: 2,
+                prizes: vec![100],
+                fleet: vec![1],
+                width: 3,
+                height: 3,
+                turn_duration: None,
+            },
+            &[]
+        ).unwrap();
+        for player in [&player1_addr, &player2_addr] {
+            send_stake(
+                &mut app,
+                &cw20_addr,
+                &game_addr,
+                player,
+                100,
+                &ReceiveMsg::RegisterTournament { tournament_id: 1 }
+            ).unwrap();
+        }
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::StartTournament { tournament_id: 1 }, &[])
+            .unwrap();
+        let game_id = 2;
+
+        // anyone can expire the match once the commit deadline passed without a board
+        let expire = |app: &mut App| app.execute_contract(
+            "anyone".into_addr(),
+            game_addr.clone(),
+            &ExecuteMsg::ExpireMatch { game_id },
+            &[]
+        );
+        let err = expire(&mut app).unwrap_err();
+        assert_eq!(ContractError::TurnNotExpired {  }, err.downcast().unwrap());
+        app.update_block(|b| b.time = b.time.plus_seconds(61));
+        let balances = [&player1_addr, &player2_addr].map(|player| balance(&app, &cw20_addr, player));
+        let response = expire(&mut app).unwrap();
+        assert!(response.has_event(
+            &Event::new("wasm-match_decided")
+                .add_attribute("tournament_id", "1")
+                .add_attribute("round", "1")
+                .add_attribute("winner", "none")
+        ));
+        let err = expire(&mut app).unwrap_err();
+        assert_eq!(ContractError::GameStarted {  }, err.downcast().unwrap());
+
+        // both players are out in the first round and share the prize
+        let response: TournamentResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetTournament { tournament_id: 1 })
+            .unwrap();
+        assert_eq!(response.status, TournamentStatusResponse::Finished);
+        assert_eq!(balance(&app, &cw20_addr, &player1_addr), balances[0] + Uint128::new(100));
+        assert_eq!(balance(&app, &cw20_addr, &player2_addr), balances[1] + Uint128::new(100));
 
-        assert_eq!(ContractError::WrongTurn {  }, err.downcast().unwrap())
+        // the game itself ends without stakes to settle
+        let state: GameStateResponse = app
+            .wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id })
+            .unwrap();
+        assert!(state.finished && state.settled);
     }
 
     #[test]
-    fn should_throw_game_started_error() {
+    fn binary_proofs() {
         let player1_addr = "player1".into_addr();
         let player2_addr = "player2".into_addr();
-        let (_, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        // a single ship on a 10x10 board, seven proof steps per field
+        let board = |format: ProofFormat, seed: &str| {
+            let mut fields = vec![vec![false; 10]; 10];
+            fields[9][9] = true;
+            MockBoard::with_format(fields, seed, format)
+        };
+        let (hex1, hex2) = (board(ProofFormat::Hex, "player1"), board(ProofFormat::Hex, "player2"));
+        let (binary1, binary2) = (board(ProofFormat::Binary, "player1"), board(ProofFormat::Binary, "player2"));
+
+        // game 2 keeps the hex format, game 3 uses binary proofs
+        for (proof_format, board1) in [(None, &hex1), (Some(ProofFormat::Binary), &binary1)] {
+            let create_game = ExecuteMsg::CreateGame {
+                stake: Uint128::new(1000),
+                fleet: vec![1],
+                width: 10,
+                height: 10,
+                turn_duration: None,
+                board: board1.root(),
+                invitee: None,
+                min_rating: None,
+                max_rating: None,
+                token_address: None,
+                denom: None,
+                proof_format,
+                salvo: None,
+                extra_turn: None,
+                weapons: None,
+            };
+            app.execute_contract(player1_addr.clone(), game_addr.clone(), &create_game, &[]).unwrap();
+        }
+        for (game_id, board2) in [(2, &hex2), (3, &binary2)] {
+            let join_game = ExecuteMsg::JoinGame { game_id, board: board2.root() };
+            app.execute_contract(player2_addr.clone(), game_addr.clone(), &join_game, &[]).unwrap();
+            deposit_stakes(&mut app, &cw20_addr, &game_addr, game_id);
+        }
 
-        let _ = app
-            .execute_contract(
-                player1_addr.clone(), 
-                game_addr.clone(), 
-                &ExecuteMsg::StartGame {}, 
-                &[]
-        ).unwrap();
+        let config: GameConfigResponse = app.wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameConfig { game_id: 3 })
+            .unwrap();
+        assert_eq!(config.proof_format, ProofFormat::Binary);
 
-        let err = app
-            .execute_contract(
-                player1_addr.clone(), 
-                game_addr.clone(), 
-                &ExecuteMsg::StartGame {}, 
-                &[]
-        ).unwrap_err();
-        assert_eq!(ContractError::GameStarted {  }, err.downcast().unwrap())
+        // proofs are only accepted in the format of their game
+        for msg in [hex2.play(3, (0, 0)), binary2.play(2, (0, 0))] {
+            let err = app.execute_contract(player1_addr.clone(), game_addr.clone(), &msg, &[]).unwrap_err();
+            assert_eq!(ContractError::InvalidProof {}, err.downcast().unwrap());
+        }
+
+        // the same shot answered in both games, multi-test does not meter gas so this
+        // compares the size of the executed messages, which the chain charges per byte
+        let mut answer_sizes = vec![];
+        for (game_id, board2) in [(2, &hex2), (3, &binary2)] {
+            let fire = ExecuteMsg::Fire { game_id, field: (0, 0) };
+            app.execute_contract(player1_addr.clone(), game_addr.clone(), &fire, &[]).unwrap();
+            let answer = board2.answer(game_id, (0, 0));
+            app.execute_contract(player2_addr.clone(), game_addr.clone(), &answer, &[]).unwrap();
+            answer_sizes.push(to_json_vec(&answer).unwrap().len());
+        }
+        assert!(answer_sizes[1] < answer_sizes[0]);
+
+        // the binary game is won, revealed and settled like any other
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &binary1.play(3, (0, 0)), &[]).unwrap();
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &binary2.play(3, (9, 9)), &[]).unwrap();
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &binary1.reveal(3), &[]).unwrap();
+        app.update_block(|b| b.time = b.time.plus_seconds(301));
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &ExecuteMsg::Settle { game_id: 3 }, &[])
+            .unwrap();
+
+        let state: GameStateResponse = app.wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 3 })
+            .unwrap();
+        assert_eq!((state.winner, state.settled), (Some(player1_addr), true));
     }
 
     #[test]
-    fn should_throw_turn_expired_error() {
+    fn salvo() {
         let player1_addr = "player1".into_addr();
         let player2_addr = "player2".into_addr();
-        let (_, game_addr, mut app) = init_app(
-            player1_addr.clone(),
-            player2_addr.clone()
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        let board1 = MockBoard::new(
+            vec![
+                vec![true, true, false, false],
+                vec![false, false, false, false],
+                vec![false, false, false, false],
+                vec![false, false, false, true],
+            ],
+            "player1",
+        );
+        let board2 = MockBoard::new(
+            vec![
+                vec![false, false, false, true],
+                vec![false, false, false, false],
+                vec![true, false, false, false],
+                vec![true, false, false, false],
+            ],
+            "player2",
         );
 
-        // start game
-        app
-            .execute_contract(
-                player1_addr.clone(), 
-                game_addr.clone(), 
-                &ExecuteMsg::StartGame {}, 
-                &[]
+        let create_game = |salvo| ExecuteMsg::CreateGame {
+            stake: Uint128::new(1000),
+            fleet: vec![2, 1],
+            width: 4,
+            height: 4,
+            turn_duration: None,
+            board: board1.root(),
+            invitee: None,
+            min_rating: None,
+            max_rating: None,
+            token_address: None,
+            denom: None,
+            proof_format: None,
+            salvo: Some(salvo),
+            extra_turn: None,
+            weapons: None,
+        };
+        for salvo in [Salvo::Fixed(0), Salvo::Fixed(17)] {
+            let err = app
+                .execute_contract(player1_addr.clone(), game_addr.clone(), &create_game(salvo), &[])
+                .unwrap_err();
+            assert_eq!(ContractError::InvalidSalvo {}, err.downcast().unwrap());
+        }
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &create_game(Salvo::Ships), &[])
+            .unwrap();
+        app.execute_contract(
+            player2_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::JoinGame { game_id: 2, board: board2.root() },
+            &[]
         ).unwrap();
-
-        app.update_block(|b| b.time = b.time.plus_seconds(1000));
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 2);
 
         let err = app
-            .execute_contract(
-                "player1".into_addr(),
-                game_addr.clone(),
-                &ExecuteMsg::Play {
-                    field: (1, 0),
-                    value: false,
-                    proof: vec![]
-                },
-                &[]
-            )
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &player2_board().salvo(1, &[(0, 0)]), &[])
             .unwrap_err();
-        assert_eq!(ContractError::TurnExpired {  }, err.downcast().unwrap());
-    }
-
-    #[test]
-    fn should_throw_unauthorized_error() {
-        let player1_addr = "player1".into_addr();
-        let player2_addr = "player2".into_addr();
-        let (_, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
-
+        assert_eq!(ContractError::NotSalvoGame {}, err.downcast().unwrap());
         let err = app
-            .execute_contract(
-                "attacker".into_addr(), 
-                game_addr.clone(), 
-                &ExecuteMsg::StartGame {}, 
-                &[]
-        ).unwrap_err();
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &board2.play(2, (0, 0)), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::SalvoGame {}, err.downcast().unwrap());
+
+        // one shot per ship, every field at most once
+        for fields in [vec![], vec![(0, 0), (0, 1), (0, 2)]] {
+            let err = app
+                .execute_contract(player1_addr.clone(), game_addr.clone(), &board2.salvo(2, &fields), &[])
+                .unwrap_err();
+            assert_eq!(ContractError::InvalidSalvo {}, err.downcast().unwrap());
+        }
+        let err = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &board2.salvo(2, &[(1, 1), (1, 1)]), &[])
+            .unwrap_err();
+        assert_eq!(ContractError::AlreadyFired {}, err.downcast().unwrap());
 
-        assert_eq!(ContractError::Unauthorized {  }, err.downcast().unwrap())
-    }
+        let response = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &board2.salvo(2, &[(0, 3), (0, 2)]), &[])
+            .unwrap();
+        assert!(response.has_event(&Event::new("wasm-ship_sank").add_attribute("sank", "(0, 3)")));
+        assert!(response.has_event(&Event::new("wasm-ship_missed").add_attribute("missed", "(0, 2)")));
+        let state: GameStateResponse = app.wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 2 })
+            .unwrap();
+        assert_eq!((state.turn, state.moves), (player2_addr.clone(), 2));
 
-    #[test]
-    fn should_throw_game_not_started_error() {
-        let player1_addr = "player1".into_addr();
-        let player2_addr = "player2".into_addr();
-        let (_, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &board1.salvo(2, &[(3, 3), (2, 2)]), &[])
+            .unwrap();
+        app.execute_contract(player1_addr.clone(), game_addr.clone(), &board2.salvo(2, &[(1, 3), (1, 0)]), &[])
+            .unwrap();
 
+        // player1 knows the single ship of player2 at (0, 3) is sunk, one ship left to fire with
         let err = app
-            .execute_contract(
-                "player2".into_addr(),
-                game_addr.clone(),
-                &ExecuteMsg::Play {
-                    field: (1, 0),
-                    value: false,
-                    proof: vec![]
-                },
-                &[]
-            )
+            .execute_contract(player2_addr.clone(), game_addr.clone(), &board1.salvo(2, &[(1, 1), (2, 1)]), &[])
             .unwrap_err();
+        assert_eq!(ContractError::InvalidSalvo {}, err.downcast().unwrap());
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &board1.salvo(2, &[(1, 1)]), &[])
+            .unwrap();
 
-        assert_eq!(ContractError::GameNotStarted {  }, err.downcast().unwrap())
+        // both remaining hits land in the last salvo
+        let response = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &board2.salvo(2, &[(2, 0), (3, 0)]), &[])
+            .unwrap();
+        assert!(response.has_event(&Event::new("wasm-game_won").add_attribute("sank", "(3, 0)")));
+        let state: GameStateResponse = app.wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 2 })
+            .unwrap();
+        assert_eq!((state.finished, state.winner, state.moves), (true, Some(player1_addr), 9));
     }
 
     #[test]
-    fn timeout_win() {
+    fn extra_turn_after_hit() {
         let player1_addr = "player1".into_addr();
         let player2_addr = "player2".into_addr();
-        let (cw20_addr, game_addr, mut app) = init_app(
-            player1_addr.clone(),
-            player2_addr.clone()
-        );
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
 
-        // start game
-        app
-            .execute_contract(
-                player1_addr.clone(), 
-                game_addr.clone(), 
-                &ExecuteMsg::StartGame {}, 
-                &[]
+        let board1 = MockBoard::new(
+            vec![vec![false; 3], vec![false; 3], vec![false, true, true]],
+            "player1",
+        );
+        let board2 = MockBoard::new(
+            vec![vec![true, true, false], vec![false; 3], vec![false; 3]],
+            "player2",
+        );
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::CreateGame {
+                stake: Uint128::new(1000),
+                fleet: vec![2],
+                width: 3,
+                height: 3,
+                turn_duration: None,
+                board: board1.root(),
+                invitee: None,
+                min_rating: None,
+                max_rating: None,
+                token_address: None,
+                denom: None,
+                proof_format: None,
+                salvo: None,
+                extra_turn: Some(true),
+                weapons: None,
+            },
+            &[]
         ).unwrap();
-
-        app.update_block(|b| b.time = b.time.plus_seconds(1000));
-
-        let response = app.execute_contract(
+        app.execute_contract(
             player2_addr.clone(),
             game_addr.clone(),
-            &ExecuteMsg::TimeoutWin {},
+            &ExecuteMsg::JoinGame { game_id: 2, board: board2.root() },
             &[]
         ).unwrap();
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 2);
 
-        let wasm = response
-            .events.iter()
-            .find(|ev| ev.ty == "wasm")
+        let config: GameConfigResponse = app.wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameConfig { game_id: 2 })
             .unwrap();
-        assert_eq!(
-            wasm.attributes
-                .iter()
-                .find(|attr| attr.key == "action")
+        assert!(config.extra_turn);
+        let state = |app: &App| -> GameStateResponse {
+            app.wrap()
+                .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 2 })
                 .unwrap()
-                .value,
-            "timeout_check"
-        );
+        };
 
-        let wasm = response
-            .events.iter()
-            .find(|ev| ev.ty == "wasm")
+        // a hit keeps the turn and restarts its timer
+        app.update_block(|b| b.time = b.time.plus_seconds(40));
+        let response = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &board2.play(2, (0, 0)), &[])
             .unwrap();
-        assert_eq!(
-            wasm.attributes
-                .iter()
-                .find(|attr| attr.key == "winner")
-                .unwrap()
-                .value,
-            player2_addr.clone().to_string()
-        );
-
-        assert_eq!(
-            Uint128::from_str(
-                &wasm.attributes
-                .iter()
-                .find(|a| a.key == "payout")
-                .unwrap()
-                .value
-            ).unwrap(),
-            Uint128::new(1900)
-        );
+        let now = app.block_info().time.seconds();
+        assert!(response.has_event(
+            &Event::new("wasm-extra_turn")
+                .add_attribute("player", player1_addr.to_string())
+                .add_attribute("deadline", (now + config.turn_duration).to_string())
+        ));
+        let response = state(&app);
+        assert_eq!((response.turn, response.last_turn_time), (player1_addr.clone(), now));
+
+        // a miss passes it on, in time only thanks to the restarted timer
+        app.update_block(|b| b.time = b.time.plus_seconds(40));
+        let response = app
+            .execute_contract(player1_addr.clone(), game_addr.clone(), &board2.play(2, (1, 0)), &[])
+            .unwrap();
+        assert!(!response.events.iter().any(|ev| ev.ty == "wasm-extra_turn"));
+        assert_eq!(state(&app).turn, player2_addr);
 
-        assert_eq!(
-            Uint128::from_str(
-                &wasm.attributes
-                .iter()
-                .find(|a| a.key == "fee_retained")
-                .unwrap()
-                .value
-            ).unwrap(),
-            Uint128::new(100)
-        );
+        app.execute_contract(player2_addr.clone(), game_addr.clone(), &board1.play(2, (2, 1)), &[]).unwrap();
+        let response = app
+            .execute_contract(player2_addr.clone(), game_addr.clone(), &board1.play(2, (2, 2)), &[])
+            .unwrap();
+        assert!(response.has_event(&Event::new("wasm-game_won").add_attribute("sank", "(2, 2)")));
+        assert_eq!(state(&app).winner, Some(player2_addr));
+    }
 
-        assert_eq!(
-            Uint128::from_str(
-                &wasm.attributes
-                .iter()
-                .find(|a| a.key == "minted_reward")
-                .unwrap()
-                .value
-            ).unwrap(),
-            Uint128::new(19)
+    #[test]
+    fn special_weapons() {
+        let player1_addr = "player1".into_addr();
+        let player2_addr = "player2".into_addr();
+        let (cw20_addr, game_addr, mut app) = init_app(player1_addr.clone(), player2_addr.clone());
+
+        let board1 = MockBoard::new(
+            vec![
+                vec![true, true, false, false],
+                vec![false, false, false, false],
+                vec![false, false, false, false],
+                vec![false, false, false, true],
+            ],
+            "player1",
         );
-
-        let game_won: Vec<_> = response
-            .events
-            .iter()
-            .filter(|ev| ev.ty == "wasm-game_won")
-            .collect();
-        assert_eq!(
-            game_won[0]
-                .attributes
-                .iter()
-                .find(|attr| attr.key == "sank")
-                .unwrap()
-                .value,
-            stringify!((-1, -1))
+        let board2 = MockBoard::new(
+            vec![
+                vec![false, false, false, false],
+                vec![false, true, true, false],
+                vec![false, false, false, false],
+                vec![true, false, false, false],
+            ],
+            "player2",
         );
-
-        let contract_balance: cw20::BalanceResponse = app.wrap()
-            .query_wasm_smart(
-                cw20_addr.clone(),
-                &Cw20QueryMsg::Balance {
-                    address: game_addr.to_string(),
-            }
+        app.execute_contract(
+            player1_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::CreateGame {
+                stake: Uint128::new(1000),
+                fleet: vec![2, 1],
+                width: 4,
+                height: 4,
+                turn_duration: None,
+                board: board1.root(),
+                invitee: None,
+                min_rating: None,
+                max_rating: None,
+                token_address: None,
+                denom: None,
+                proof_format: None,
+                salvo: None,
+                extra_turn: None,
+                weapons: Some(Weapons { bombs: 1, radars: 1 }),
+            },
+            &[]
         ).unwrap();
-        assert_eq!(contract_balance.balance, Uint128::new(100));
-
-        let winner_balance: cw20::BalanceResponse = app.wrap()
-            .query_wasm_smart(
-                cw20_addr.clone(),
-                &Cw20QueryMsg::Balance {
-                    address: player2_addr.to_string(),
-        },
-        )
-        .unwrap();
-
-        assert_eq!(winner_balance.balance, Uint128::new(1_000_000 - 1_000 + 1919));
-
-        let token_info: cw20::TokenInfoResponse = app.wrap()
-            .query_wasm_smart(
-                cw20_addr.clone(),
-                &Cw20QueryMsg::TokenInfo {},
-            )
-        .unwrap();
-
-        assert_eq!(token_info.total_supply, Uint128::new(2_000_000 + 19));
+        app.execute_contract(
+            player2_addr.clone(),
+            game_addr.clone(),
+            &ExecuteMsg::JoinGame { game_id: 2, board: board2.root() },
+            &[]
+        ).unwrap();
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 2);
+
+        let bomb = |board: &MockBoard, game_id, center, fields: &[(usize, usize)]| ExecuteMsg::Bomb {
+            game_id,
+            center,
+            shots: fields.iter().map(|&field| board.shot(field)).collect(),
+        };
+        let radar = |start, length, vertical| ExecuteMsg::Radar {
+            game_id: 2,
+            segment: Segment { start, length, vertical },
+        };
+        let answer = |found| ExecuteMsg::AnswerScan { game_id: 2, found };
+        let area: Vec<_> = (0..3).flat_map(|row| (0..3).map(move |column| (row, column))).collect();
+        let execute = |app: &mut App, sender: &Addr, msg: &ExecuteMsg| {
+            app.execute_contract(sender.clone(), game_addr.clone(), msg, &[])
+        };
+
+        // games come without weapons by default
+        let err = execute(&mut app, &player1_addr, &bomb(&player2_board(), 1, (1, 1), &area)).unwrap_err();
+        assert_eq!(ContractError::NoWeaponUses {}, err.downcast().unwrap());
+
+        // a bomb needs a proof for every field of its area on the board
+        for (center, fields) in [((1, 1), &area[1..]), ((3, 3), &area[..])] {
+            let err = execute(&mut app, &player1_addr, &bomb(&board2, 2, center, fields)).unwrap_err();
+            assert_eq!(ContractError::InvalidTarget {}, err.downcast().unwrap());
+        }
 
-        let err = app
-            .execute_contract(
-                "player1".into_addr(),
-                game_addr.clone(),
-                &ExecuteMsg::Play { 
-                    field: (1, 0),
-                    value: true,
-                    proof: vec![]
-                },
-                &[]
-            )
-            .unwrap_err();
+        let response = execute(&mut app, &player1_addr, &bomb(&board2, 2, (1, 1), &area)).unwrap();
+        assert!(response.has_event(&Event::new("wasm-ship_sank").add_attribute("sank", "(1, 1)")));
+        assert!(response.has_event(&Event::new("wasm-ship_sank").add_attribute("sank", "(1, 2)")));
+        assert_eq!(response.events.iter().filter(|ev| ev.ty == "wasm-ship_missed").count(), 7);
 
-        assert_eq!(ContractError::GameFinished {  }, err.downcast().unwrap())
+        let players: PlayersResponse = app.wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetPlayers { game_id: 2 })
+            .unwrap();
+        let player1 = players.players.iter().find(|player| player.address == player1_addr).unwrap();
+        assert_eq!(player1.weapons_used, Weapons { bombs: 1, radars: 0 });
 
+        // a radar scan is answered by the defender without proving any field, and uses the turn
+        for (start, length) in [((3, 1), 4), ((3, 1), 0), ((0, 0), usize::MAX), ((4, 0), 1), ((usize::MAX, 0), 2)] {
+            let err = execute(&mut app, &player2_addr, &radar(start, length, false)).unwrap_err();
+            assert_eq!(ContractError::InvalidField {}, err.downcast().unwrap());
+        }
+        execute(&mut app, &player2_addr, &radar((3, 1), 3, false)).unwrap();
+        let err = execute(&mut app, &player1_addr, &board2.play(2, (3, 0))).unwrap_err();
+        assert_eq!(ContractError::ShotPending {}, err.downcast().unwrap());
+        // player1 hides the ship at (3, 3), which only shows once the boards are revealed
+        let response = execute(&mut app, &player1_addr, &answer(false)).unwrap();
+        assert!(response.has_event(
+            &Event::new("wasm-radar_scan")
+                .add_attribute("defender", player1_addr.to_string())
+                .add_attribute("start", "(3, 1)")
+                .add_attribute("length", "3")
+                .add_attribute("vertical", "false")
+                .add_attribute("found", "false")
+        ));
+        let state: GameStateResponse = app.wrap()
+            .query_wasm_smart(game_addr.clone(), &QueryMsg::GetGameState { game_id: 2 })
+            .unwrap();
+        assert_eq!((state.turn, state.moves, state.pending_scan), (player1_addr.clone(), 9, None));
+        let err = execute(&mut app, &player1_addr, &answer(true)).unwrap_err();
+        assert_eq!(ContractError::NoPendingScan {}, err.downcast().unwrap());
+
+        let err = execute(&mut app, &player1_addr, &bomb(&board2, 2, (3, 0), &[(2, 3)])).unwrap_err();
+        assert_eq!(ContractError::NoWeaponUses {}, err.downcast().unwrap());
+        execute(&mut app, &player1_addr, &radar((2, 0), 2, true)).unwrap();
+        let response = execute(&mut app, &player2_addr, &answer(true)).unwrap();
+        assert!(response.events.iter().any(|ev| {
+            ev.ty == "wasm-radar_scan" && ev.attributes.iter().any(|attr| attr.key == "found" && attr.value == "true")
+        }));
+
+        let err = execute(&mut app, &player2_addr, &radar((0, 3), 4, true)).unwrap_err();
+        assert_eq!(ContractError::NoWeaponUses {}, err.downcast().unwrap());
+        execute(&mut app, &player2_addr, &board1.play(2, (2, 2))).unwrap();
+
+        // the last ship is sunk with a regular shot
+        let response = execute(&mut app, &player1_addr, &board2.play(2, (3, 0))).unwrap();
+        assert!(response.has_event(&Event::new("wasm-game_won").add_attribute("sank", "(3, 0)")));
+
+        // the false answer makes the winner's board illegal, the pot goes to the loser
+        let response = execute(&mut app, &player1_addr, &board1.reveal(2)).unwrap();
+        assert!(response.has_event(&Event::new("wasm").add_attribute("legal", "false")));
+        execute(&mut app, &player2_addr, &board2.reveal(2)).unwrap();
+        let response = execute(&mut app, &player2_addr, &ExecuteMsg::Settle { game_id: 2 }).unwrap();
+        assert!(response.has_event(
+            &Event::new("wasm-slashed")
+                .add_attribute("game_id", "2")
+                .add_attribute("player", player1_addr.to_string())
+        ));
+        assert!(response.has_event(&Event::new("wasm").add_attribute("recipient", player2_addr.to_string())));
     }
 
     #[test]
     fn should_throw_turn_not_expired() {
         let player1_addr = "player1".into_addr();
         let player2_addr = "player2".into_addr();
-        let (_, game_addr, mut app) = init_app(
+        let (cw20_addr, game_addr, mut app) = init_app(
             player1_addr.clone(),
             player2_addr.clone()
         );
 
-        app
-            .execute_contract(
-                player1_addr.clone(), 
-                game_addr.clone(), 
-                &ExecuteMsg::StartGame {}, 
-                &[]
-        ).unwrap();
+        deposit_stakes(&mut app, &cw20_addr, &game_addr, 1);
 
         let err = app.execute_contract(
             player2_addr.clone(),
             game_addr.clone(),
-            &ExecuteMsg::TimeoutWin {},
+            &ExecuteMsg::TimeoutWin { game_id: 1 },
             &[]
         ).unwrap_err();
 
@@ -1,7 +1,9 @@
+mod betting;
 pub mod contract;
 mod error;
 pub mod integration_tests;
 pub mod msg;
 pub mod state;
+mod tournament;
 
 pub use crate::error::ContractError;
\ No newline at end of file
@@ -1,49 +1,535 @@
-use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Uint128;
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary, Decimal, Int128, Uint128};
 
-use crate::state::Player;
-
-#[cw_serde]
-pub struct PlayerInstantiate {
-    pub address: String,
-    pub stake: Uint128,
-    pub board: String,
-}
+use crate::state::{FeeRoute, ProofFormat, Salvo, Segment, StakeAsset, Weapons};
+use cw20::Cw20ReceiveMsg;
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub token_address: String,
-    pub ships: usize,
-    pub players: Vec<PlayerInstantiate>,
+    // defaults to the instantiating address
+    pub admin: Option<String>,
+    pub min_stake: Option<Uint128>,
+    pub fee_percentage: Option<u64>,
+    pub reward_percentage: Option<u64>,
+    pub turn_duration: Option<u64>,
+    // defaults to accruing the fees in the contract
+    pub fee_route: Option<FeeRoute>,
+    pub treasury: Option<String>,
+    // bank denom accepted for stakes, native staking is disabled without it
+    pub native_denom: Option<String>,
+    pub native_min_stake: Option<Uint128>,
+    pub betting_moves: Option<u32>,
 }
 
 #[cw_serde]
+#[derive(QueryResponses)]
 pub enum QueryMsg {
-    GetPlayers {},
-    GetGameConfig {},
-    GetGameState {}
-}
-
-#[cw_serde]
-pub struct ProofStep {
-    pub hash: String,
-    pub is_left: bool,
+    /// Returns both players of the game with their stakes and board commitments.
+    #[returns(PlayersResponse)]
+    GetPlayers { game_id: u64 },
+    /// Returns the rules the game was created with.
+    #[returns(GameConfigResponse)]
+    GetGameConfig { game_id: u64 },
+    /// Returns whose turn it is and how far the game got.
+    #[returns(GameStateResponse)]
+    GetGameState { game_id: u64 },
+    /// Returns the parameters applied to newly created games.
+    #[returns(ConfigResponse)]
+    GetConfig {},
+    /// Returns the fees retained by the contract per token.
+    #[returns(AccruedFeesResponse)]
+    GetAccruedFees {},
+    /// Returns the opponent's board as the given player knows it.
+    #[returns(BoardViewResponse)]
+    GetBoardView {
+        game_id: u64,
+        player: String,
+    },
+    /// Returns the shots of the game in order. Supports pagination.
+    #[returns(MovesResponse)]
+    GetMoves {
+        game_id: u64,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Returns the elo rating of a player.
+    #[returns(RatingResponse)]
+    GetRating { address: String },
+    /// Returns the statistics of a player over all settled games.
+    #[returns(PlayerStatsResponse)]
+    GetPlayerStats { address: String },
+    /// Returns the players with the most wins or the highest earnings first.
+    /// Supports pagination.
+    #[returns(LeaderboardResponse)]
+    Leaderboard {
+        sort_by: LeaderboardSort,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the games waiting for an opponent. Supports pagination.
+    #[returns(OpenGamesResponse)]
+    GetOpenGames {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the partner tokens games can be staked in besides the configured one.
+    /// Supports pagination.
+    #[returns(AcceptedTokensResponse)]
+    GetAcceptedTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns how much was bet on each player and whether betting is still open.
+    #[returns(BetsResponse)]
+    GetBets { game_id: u64 },
+    /// Returns the bet of a spectator.
+    #[returns(BetResponse)]
+    GetBet {
+        game_id: u64,
+        bettor: String,
+    },
+    /// Returns the registered players and the matches of the current round.
+    #[returns(TournamentResponse)]
+    GetTournament { tournament_id: u64 },
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    StartGame {},
+    CreateGame {
+        stake: Uint128,
+        fleet: Vec<usize>,
+        width: usize,
+        height: usize,
+        turn_duration: Option<u64>,
+        board: String,
+        invitee: Option<String>,
+        // rating range the joining player has to be in
+        min_rating: Option<u32>,
+        max_rating: Option<u32>,
+        // stakes in an accepted partner token instead of the configured one
+        token_address: Option<String>,
+        // stakes in the configured native denom instead of the token
+        denom: Option<String>,
+        // defaults to hex, both players have to commit in the chosen format
+        proof_format: Option<ProofFormat>,
+        // several shots per turn, fired with PlaySalvo
+        salvo: Option<Salvo>,
+        // defaults to false, the turn passes after every shot
+        extra_turn: Option<bool>,
+        // defaults to no special weapons
+        weapons: Option<Weapons>,
+    },
+    // stakes of native games can be sent along
+    JoinGame {
+        game_id: u64,
+        board: String,
+    },
+    Receive(Cw20ReceiveMsg),
+    // stake of a native game sent along with the message
+    Deposit { game_id: u64 },
+    Withdraw { game_id: u64 },
     Play {
+        game_id: u64,
         field: (usize, usize),
         value: bool,
-        proof: Vec<ProofStep>
+        salt: String,
+        proof: Proof,
+    },
+    // every shot of a salvo game's turn at once, the turn passes after all are resolved
+    PlaySalvo {
+        game_id: u64,
+        shots: Vec<Shot>,
     },
-    TimeoutWin {},
+    // special weapon, one shot with a proof for every field of the 3x3 area
+    // around `center` on the board and not fired at before
+    Bomb {
+        game_id: u64,
+        center: (usize, usize),
+        shots: Vec<Shot>,
+    },
+    // special weapon, asks the opponent whether any field of the segment holds a ship
+    Radar {
+        game_id: u64,
+        segment: Segment,
+    },
+    // the defender's answer to a radar scan, checked against the board revealed after the game
+    AnswerScan {
+        game_id: u64,
+        found: bool,
+    },
+    Fire {
+        game_id: u64,
+        field: (usize, usize),
+    },
+    Answer {
+        game_id: u64,
+        value: bool,
+        salt: String,
+        proof: Proof,
+    },
+    TimeoutWin { game_id: u64 },
+    Resign { game_id: u64 },
+    OfferDraw { game_id: u64 },
+    AcceptDraw { game_id: u64 },
+    // before the game starts, refunds every deposit
+    Cancel { game_id: u64 },
+    Reveal {
+        game_id: u64,
+        board: Vec<Vec<bool>>,
+        salts: Vec<Vec<String>>,
+    },
+    Settle { game_id: u64 },
+    // admin only, applies to games created afterwards
+    UpdateConfig {
+        admin: Option<String>,
+        min_stake: Option<Uint128>,
+        fee_percentage: Option<u64>,
+        reward_percentage: Option<u64>,
+        turn_duration: Option<u64>,
+        fee_route: Option<FeeRoute>,
+        treasury: Option<String>,
+        native_denom: Option<String>,
+        native_min_stake: Option<Uint128>,
+        betting_moves: Option<u32>,
+    },
+    // pays out a bet once the game is settled, refunds it if the game had no winner
+    ClaimBet { game_id: u64 },
+    // admin only, accepts a partner token for stakes or updates its settings
+    AddToken {
+        token_address: String,
+        min_stake: Uint128,
+        // only if the contract is the token's minter
+        mint_reward: bool,
+    },
+    // admin only, games already staked in the token are not affected
+    RemoveToken { token_address: String },
+    // admin only, withdraws native fees in `denom` if set, defaults to the configured token
+    WithdrawFees {
+        token_address: Option<String>,
+        denom: Option<String>,
+        recipient: String,
+        amount: Uint128,
+    },
+    // players register by sending the entry fee with ReceiveMsg::RegisterTournament
+    CreateTournament {
+        entry_fee: Uint128,
+        max_players: u32,
+        // percentages of the prize pool for the first, second, ... place
+        prizes: Vec<u64>,
+        fleet: Vec<usize>,
+        width: usize,
+        height: usize,
+        turn_duration: Option<u64>,
+    },
+    // creator only, closes the registration and seeds the bracket
+    StartTournament { tournament_id: u64 },
+    // creator only, before the start, refunds every entry fee
+    CancelTournament { tournament_id: u64 },
+    // board commitment of a player in a tournament game
+    CommitBoard {
+        game_id: u64,
+        board: String,
+    },
+    // ends a tournament game neither player committed a board for in time
+    ExpireMatch { game_id: u64 },
+}
+
+/// Sibling path of a field from its leaf up to the board root, in the
+/// format of the game it is sent to.
+#[cw_serde]
+#[serde(untagged)]
+pub enum Proof {
+    // hex encoded nodes
+    Hex(Vec<String>),
+    // 32 byte nodes concatenated
+    Binary(Binary),
+}
+
+impl From<Vec<String>> for Proof {
+    fn from(nodes: Vec<String>) -> Self {
+        Proof::Hex(nodes)
+    }
+}
+
+/// Proven shot of a salvo.
+#[cw_serde]
+pub struct Shot {
+    pub field: (usize, usize),
+    pub value: bool,
+    pub salt: String,
+    pub proof: Proof,
+}
+
+/// Hook messages accepted with a stake sent through the token contract.
+#[cw_serde]
+pub enum ReceiveMsg {
+    JoinGame {
+        game_id: u64,
+        board: String,
+    },
+    Deposit { game_id: u64 },
+    RegisterTournament { tournament_id: u64 },
+    // spectator bet on `player` winning the game
+    PlaceBet {
+        game_id: u64,
+        player: String,
+    },
+}
+
+/// Games stored in a legacy layout are rewritten in the current one, fields
+/// missing from older layouts get their defaults when loaded. Games are
+/// migrated in batches, while a `last_game_id` attribute is returned the
+/// migration has to be run again with it as `start_after`.
+#[cw_serde]
+pub struct MigrateMsg {
+    pub start_after: Option<u64>,
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct PlayerInfo {
+    pub address: Addr,
+    pub stake: Uint128,
+    pub deposited: bool,
+    // merkle root of the board the player committed to
+    pub board_root: String,
+    // own fields hit by the opponent
+    pub sank: Vec<(usize, usize)>,
+    pub revealed: Option<Vec<Vec<bool>>>,
+    pub weapons_used: Weapons,
+    // radar scans of the own board and the answers given
+    pub scans: Vec<ScanResponse>,
+}
+
+#[cw_serde]
+pub struct ScanResponse {
+    pub segment: Segment,
+    pub found: bool,
 }
 
 #[cw_serde]
 pub struct PlayersResponse {
-    pub players: Vec<Player>
+    pub players: Vec<PlayerInfo>
 }
 
+#[cw_serde]
+pub struct GameConfigResponse {
+    pub asset: StakeAsset,
+    pub creator: Addr,
+    pub invitee: Option<Addr>,
+    pub stake: Uint128,
+    pub fleet: Vec<usize>,
+    // number of fields that have to be hit to win
+    pub fleet_size: usize,
+    pub width: usize,
+    pub height: usize,
+    pub turn_duration: u64,
+    pub fee_percentage: u64,
+    pub reward_percentage: u64,
+    pub min_rating: Option<u32>,
+    pub max_rating: Option<u32>,
+    pub proof_format: ProofFormat,
+    pub salvo: Option<Salvo>,
+    pub extra_turn: bool,
+    pub weapons: Weapons,
+    pub betting_moves: u32,
+}
+
+#[cw_serde]
+pub struct GameStateResponse {
+    pub started: bool,
+    pub finished: bool,
+    pub settled: bool,
+    pub turn: Addr,
+    pub last_turn_time: u64,
+    pub pending_shot: Option<(usize, usize)>,
+    pub pending_scan: Option<Segment>,
+    pub draw_offer: Option<Addr>,
+    pub moves: u32,
+    pub winner: Option<Addr>,
+    pub reveal_deadline: u64,
+    pub timed_out: bool,
+    pub paid_to: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub admin: Addr,
+    pub token_address: Addr,
+    pub min_stake: Uint128,
+    pub fee_percentage: u64,
+    pub reward_percentage: u64,
+    pub turn_duration: u64,
+    pub fee_route: FeeRoute,
+    pub treasury: Option<Addr>,
+    pub native_denom: Option<String>,
+    pub native_min_stake: Uint128,
+    pub betting_moves: u32,
+}
 
+#[cw_serde]
+pub struct OpenGame {
+    pub game_id: u64,
+    pub creator: Addr,
+    pub invitee: Option<Addr>,
+    pub asset: StakeAsset,
+    pub stake: Uint128,
+    pub fleet: Vec<usize>,
+    pub width: usize,
+    pub height: usize,
+    pub turn_duration: u64,
+    pub min_rating: Option<u32>,
+    pub max_rating: Option<u32>,
+    pub proof_format: ProofFormat,
+    pub salvo: Option<Salvo>,
+    pub extra_turn: bool,
+    pub weapons: Weapons,
+}
+
+#[cw_serde]
+pub struct OpenGamesResponse {
+    pub games: Vec<OpenGame>
+}
+
+#[cw_serde]
+pub struct AcceptedTokenInfo {
+    pub token_address: Addr,
+    pub min_stake: Uint128,
+    pub mint_reward: bool,
+}
+
+#[cw_serde]
+pub struct AcceptedTokensResponse {
+    pub tokens: Vec<AcceptedTokenInfo>,
+}
+
+#[cw_serde]
+pub struct BetTotal {
+    pub player: Addr,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct BetsResponse {
+    pub open: bool,
+    pub totals: Vec<BetTotal>,
+}
+
+#[cw_serde]
+pub struct BetResponse {
+    pub player: Addr,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct AccruedFee {
+    pub asset: StakeAsset,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct AccruedFeesResponse {
+    pub fees: Vec<AccruedFee>,
+}
+
+#[cw_serde]
+pub struct MoveInfo {
+    pub index: u32,
+    pub shooter: Addr,
+    pub field: (usize, usize),
+    pub hit: bool,
+    pub time: u64,
+}
+
+#[cw_serde]
+pub struct MovesResponse {
+    pub moves: Vec<MoveInfo>,
+}
+
+#[cw_serde]
+pub enum Cell {
+    Unknown,
+    Hit,
+    Miss,
+    Sunk,
+}
+
+#[cw_serde]
+pub struct BoardViewResponse {
+    pub width: usize,
+    pub height: usize,
+    // opponent's fields in rows, as seen by the requesting player
+    pub cells: Vec<Vec<Cell>>,
+    pub remaining_ships: usize,
+}
+
+#[cw_serde]
+pub enum LeaderboardSort {
+    Wins,
+    Earnings,
+}
+
+#[cw_serde]
+pub struct PlayerStatsResponse {
+    pub address: Addr,
+    pub games_played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub timeouts: u32,
+    pub shots_fired: u32,
+    pub hits: u32,
+    // share of the shots fired that hit
+    pub accuracy: Decimal,
+    pub net_won: Int128,
+}
+
+#[cw_serde]
+pub struct LeaderboardResponse {
+    pub players: Vec<PlayerStatsResponse>,
+}
+
+#[cw_serde]
+pub struct RatingResponse {
+    pub address: Addr,
+    pub rating: u32,
+    pub games: u32,
+    // the rating still changes quickly while only a few games are counted
+    pub provisional: bool,
+}
+
+#[cw_serde]
+pub struct TournamentResponse {
+    pub tournament_id: u64,
+    pub creator: Addr,
+    pub token_address: Addr,
+    pub entry_fee: Uint128,
+    pub max_players: u32,
+    pub prizes: Vec<u64>,
+    pub fleet: Vec<usize>,
+    pub width: usize,
+    pub height: usize,
+    pub turn_duration: u64,
+    pub status: TournamentStatusResponse,
+    pub players: Vec<Addr>,
+    pub round: u32,
+    pub matches: Vec<TournamentMatchResponse>,
+}
+
+#[cw_serde]
+pub enum TournamentStatusResponse {
+    Registration,
+    Running,
+    Finished,
+    Cancelled,
+}
+
+#[cw_serde]
+pub struct TournamentMatchResponse {
+    // no player on one side means the other one has a bye
+    pub players: (Option<Addr>, Option<Addr>),
+    pub game_id: Option<u64>,
+    pub winner: Option<Addr>,
+    pub decided: bool,
+}
@@ -1,14 +1,199 @@
+use board_commitment::Encoding;
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Empty, Int128, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
 #[cw_serde]
-pub struct GameConfig {
+pub struct Config {
+    pub admin: Addr,
     pub token_address: Addr,
-    pub ships: usize,
+    pub min_stake: Uint128,
+    // share of the pot kept by the contract
+    pub fee_percentage: u64,
+    // share of the payout minted to the winner on top of it
+    pub reward_percentage: u64,
+    // turn duration of games created without their own
+    pub turn_duration: u64,
+    // what happens to the fees retained when a game is settled
+    pub fee_route: FeeRoute,
+    pub treasury: Option<Addr>,
+    // bank denom games can be staked in besides the token, none disables native stakes
+    #[serde(default)]
+    pub native_denom: Option<String>,
+    #[serde(default)]
+    pub native_min_stake: Uint128,
+    // spectators can bet on a game until this many shots were resolved
+    #[serde(default = "default_betting_moves")]
+    pub betting_moves: u32,
+}
+
+fn default_betting_moves() -> u32 {
+    DEFAULT_BETTING_MOVES
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+#[cw_serde]
+pub enum FeeRoute {
+    // kept by the contract until the admin withdraws them
+    Accrue,
+    // transferred to the treasury address
+    Treasury,
+    // burned through the token contract
+    Burn,
+}
+
+#[cw_serde]
+pub struct AcceptedToken {
+    pub min_stake: Uint128,
+    // the contract is the token's minter and mints the winner's reward in it
+    pub mint_reward: bool,
+}
+
+// accepted tokens (map): token address: partner token games can be staked in besides the configured one
+pub const ACCEPTED_TOKENS: Map<&Addr, AcceptedToken> = Map::new("accepted_tokens");
+
+// accrued fees (map): token address: fees retained and not yet withdrawn
+pub const ACCRUED_FEES: Map<&Addr, Uint128> = Map::new("accrued_fees");
+// accrued native fees (map): denom: fees retained and not yet withdrawn
+pub const ACCRUED_NATIVE_FEES: Map<&str, Uint128> = Map::new("accrued_native_fees");
+
+#[cw_serde]
+pub enum StakeAsset {
+    // bank denom, stakes are sent along with the message
+    Native { denom: String },
+    // token contract, stakes are sent through its Receive hook
+    Cw20 { address: Addr },
+}
+
+// denom or token address, as used in attributes
+impl std::fmt::Display for StakeAsset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StakeAsset::Native { denom } => write!(f, "{}", denom),
+            StakeAsset::Cw20 { address } => write!(f, "{}", address),
+        }
+    }
+}
+
+/// How the nodes of a game's board commitments are hashed and sent.
+#[cw_serde]
+#[derive(Default)]
+pub enum ProofFormat {
+    // hex strings hashed as text, as built by the Go proof generator
+    #[default]
+    Hex,
+    // raw 32 byte nodes, smaller proofs and half the hashed bytes per level
+    Binary,
+}
+
+impl ProofFormat {
+    pub fn encoding(&self) -> Encoding {
+        match self {
+            ProofFormat::Hex => Encoding::Hex,
+            ProofFormat::Binary => Encoding::Binary,
+        }
+    }
+}
+
+/// Special weapons, counted as uses allowed per player in the game config and
+/// as uses spent in the player's state.
+#[cw_serde]
+#[derive(Default)]
+pub struct Weapons {
+    // resolves a 3x3 area in one move
+    pub bombs: u32,
+    // tells whether any ship lies in a row or column segment
+    pub radars: u32,
+}
+
+/// Number of shots a player fires per turn in a salvo game.
+#[cw_serde]
+pub enum Salvo {
+    // one per own ship the opponent does not know to be sunk
+    Ships,
+    // the same number every turn
+    Fixed(u32),
+}
+
+/// Row or column segment scanned by a radar, `length` fields from `start`
+/// to the right or, if `vertical`, downwards.
+#[cw_serde]
+pub struct Segment {
+    pub start: (usize, usize),
+    pub length: usize,
+    pub vertical: bool,
+}
+
+impl Segment {
+    pub fn fields(&self) -> Vec<(usize, usize)> {
+        let (row, column) = self.start;
+        (0..self.length)
+            .map(|offset| if self.vertical { (row + offset, column) } else { (row, column + offset) })
+            .collect()
+    }
 }
 
-pub const GAME_CONFIG: Item<GameConfig> = Item::new("game_config");
+/// Radar scan of a player's board with the answer the player gave.
+#[cw_serde]
+pub struct Scan {
+    pub segment: Segment,
+    pub found: bool,
+}
+
+#[cw_serde]
+pub struct GameConfig {
+    pub asset: StakeAsset,
+    pub creator: Addr,
+    pub invitee: Option<Addr>,
+    pub stake: Uint128,
+    // lengths of the ships every player has to place
+    pub fleet: Vec<usize>,
+    pub width: usize,
+    pub height: usize,
+    pub turn_duration: u64,
+    // economic parameters in effect when the game was created
+    pub fee_percentage: u64,
+    pub reward_percentage: u64,
+    // ratings the joining player has to be within
+    #[serde(default)]
+    pub min_rating: Option<u32>,
+    #[serde(default)]
+    pub max_rating: Option<u32>,
+    // (tournament id, match index) of games played in a tournament bracket
+    #[serde(default)]
+    pub tournament: Option<(u64, usize)>,
+    #[serde(default)]
+    pub proof_format: ProofFormat,
+    // shots per turn in the salvo variant, a single shot per turn without it
+    #[serde(default)]
+    pub salvo: Option<Salvo>,
+    // a hit grants the shooter another shot, or another salvo
+    #[serde(default)]
+    pub extra_turn: bool,
+    // uses of every special weapon per player
+    #[serde(default)]
+    pub weapons: Weapons,
+    // spectators can bet on the game until this many shots were resolved
+    #[serde(default = "default_betting_moves")]
+    pub betting_moves: u32,
+}
+
+impl GameConfig {
+    /// Number of fields occupied by the whole fleet, all of which have to be hit to win.
+    pub fn fleet_size(&self) -> usize {
+        self.fleet.iter().sum()
+    }
+
+    pub fn contains(&self, field: (usize, usize)) -> bool {
+        field.0 < self.height && field.1 < self.width
+    }
+
+    /// Position of `field` among the board fields in row-major order.
+    pub fn index(&self, field: (usize, usize)) -> u32 {
+        (field.0 * self.width + field.1) as u32
+    }
+}
 
 #[cw_serde]
 pub struct GameState {
@@ -16,28 +201,227 @@ pub struct GameState {
     pub finished: bool,
     pub turn: Addr,
     pub last_turn_time: u64,
+    // field fired at with Fire, waiting for the defender's Answer
+    #[serde(default)]
+    pub pending_shot: Option<(usize, usize)>,
+    // segment scanned with Radar, waiting for the defender's AnswerScan
+    #[serde(default)]
+    pub pending_scan: Option<Segment>,
+    // player who offered a draw the opponent can accept
+    #[serde(default)]
+    pub draw_offer: Option<Addr>,
+    // number of shots resolved so far, also the index of the next move
+    #[serde(default)]
+    pub moves: u32,
+    #[serde(default)]
+    pub winner: Option<Addr>,
+    // boards can be revealed until this time, after which the game can be settled
+    #[serde(default)]
+    pub reveal_deadline: u64,
+    #[serde(default)]
+    pub settled: bool,
+    // the loser let their turn run out
+    #[serde(default)]
+    pub timed_out: bool,
+    // player the pot was paid to when the game was settled
+    #[serde(default)]
+    pub paid_to: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct Game {
+    pub config: GameConfig,
+    pub state: GameState,
 }
 
-pub const GAME_STATE: Item<GameState> = Item::new("game_state");
+// games (map): game id: game, every match hosted by the contract has its own config and state
+pub const GAMES: Map<u64, Game> = Map::new("games");
+pub const NEXT_GAME_ID: Item<u64> = Item::new("next_game_id");
+
+// games waiting for an opponent to join
+pub const OPEN_GAMES: Map<u64, Empty> = Map::new("open_games");
 
 #[cw_serde]
 pub struct Board {
     pub fields: String,
     pub sank: Vec<(usize, usize)>,
+    // full board opened by the player once the game is over
+    #[serde(default)]
+    pub revealed: Option<Vec<Vec<bool>>>,
 }
 
 #[cw_serde]
 pub struct Player {
     pub address: Addr,
     pub stake: Uint128,
+    #[serde(default)]
+    pub deposited: bool,
     pub board: Board,
+    #[serde(default)]
+    pub weapons_used: Weapons,
+    // radar scans of the own board and the answers given, checked against the revealed board
+    #[serde(default)]
+    pub scans: Vec<Scan>,
+}
+
+// game boards (map): (game id, addr): player, each player has his own staked assets and a board
+pub const PLAYERS: Map<(u64, &Addr), Player> = Map::new("players");
+
+#[cw_serde]
+pub struct Move {
+    pub index: u32,
+    pub shooter: Addr,
+    pub field: (usize, usize),
+    pub hit: bool,
+    pub time: u64,
+}
+
+// move log (map): (game id, move index): move, every resolved shot in the order it was made
+pub const MOVES: Map<(u64, u32), Move> = Map::new("moves");
+
+// fired fields (map): (game id, shooter, row * width + column), used to reject repeated shots
+pub const SHOTS: Map<(u64, &Addr, u32), Empty> = Map::new("shots");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct PlayerStats {
+    pub games_played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    // games lost by letting the turn run out
+    pub timeouts: u32,
+    pub shots_fired: u32,
+    pub hits: u32,
+    // everything paid out to the player minus the stakes they put in
+    pub net_won: Int128,
+}
+
+pub struct StatsIndexes<'a> {
+    pub wins: MultiIndex<'a, u32, PlayerStats, &'a Addr>,
+    pub earnings: MultiIndex<'a, i128, PlayerStats, &'a Addr>,
+}
+
+impl IndexList<PlayerStats> for StatsIndexes<'_> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<PlayerStats>> + '_> {
+        let v: Vec<&dyn Index<PlayerStats>> = vec![&self.wins, &self.earnings];
+        Box::new(v.into_iter())
+    }
+}
+
+// player statistics (indexed map): addr: stats over all settled games, indexed by wins and earnings
+pub fn player_stats<'a>() -> IndexedMap<&'a Addr, PlayerStats, StatsIndexes<'a>> {
+    let indexes = StatsIndexes {
+        wins: MultiIndex::new(|_, stats| stats.wins, "player_stats", "player_stats__wins"),
+        earnings: MultiIndex::new(|_, stats| stats.net_won.i128(), "player_stats", "player_stats__earnings"),
+    };
+    IndexedMap::new("player_stats", indexes)
+}
+
+#[cw_serde]
+pub struct Rating {
+    pub rating: u32,
+    // rated games played, the rating is provisional for the first few
+    pub games: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating { rating: INITIAL_RATING, games: 0 }
+    }
+}
+
+// elo ratings (map): addr: rating, updated when a game is settled or drawn
+pub const RATINGS: Map<&Addr, Rating> = Map::new("ratings");
+
+#[cw_serde]
+pub enum TournamentStatus {
+    Registration,
+    Running,
+    Finished,
+    Cancelled,
+}
+
+#[cw_serde]
+pub struct TournamentMatch {
+    // no player on one side means the other one has a bye
+    pub players: (Option<Addr>, Option<Addr>),
+    pub game_id: Option<u64>,
+    pub winner: Option<Addr>,
+    pub decided: bool,
+}
+
+#[cw_serde]
+pub struct Tournament {
+    pub creator: Addr,
+    pub token_address: Addr,
+    pub entry_fee: Uint128,
+    pub max_players: u32,
+    // percentages of the prize pool paid to the first, second, ... place
+    pub prizes: Vec<u64>,
+    pub fleet: Vec<usize>,
+    pub width: usize,
+    pub height: usize,
+    pub turn_duration: u64,
+    pub status: TournamentStatus,
+    pub players: Vec<Addr>,
+    pub round: u32,
+    // matches of the current round, in bracket order
+    pub matches: Vec<TournamentMatch>,
+    // players knocked out and the round they lost in
+    pub eliminated: Vec<(Addr, u32)>,
+}
+
+impl Tournament {
+    /// Entry fees are paid in the token configured when the tournament was created.
+    pub fn asset(&self) -> StakeAsset {
+        StakeAsset::Cw20 { address: self.token_address.clone() }
+    }
 }
 
-// game boards (map): addr: player, each player has his own staked assets and a board
-pub const PLAYERS: Map<Addr, Player> = Map::new("players");
+// tournaments (map): tournament id: single-elimination bracket and its registered players
+pub const TOURNAMENTS: Map<u64, Tournament> = Map::new("tournaments");
+pub const NEXT_TOURNAMENT_ID: Item<u64> = Item::new("next_tournament_id");
+
+// largest supported tournament bracket
+pub const MAX_TOURNAMENT_PLAYERS: u32 = 64u32;
+
+#[cw_serde]
+pub struct Bet {
+    // player the bettor backs to win
+    pub player: Addr,
+    pub amount: Uint128,
+}
+
+// bets (map): (game id, bettor): spectator bet on one of the players
+pub const BETS: Map<(u64, &Addr), Bet> = Map::new("bets");
+
+// bet totals (map): (game id, player): everything wagered on the player
+pub const BET_TOTALS: Map<(u64, &Addr), Uint128> = Map::new("bet_totals");
+
+// elo rating of players without rated games
+pub const INITIAL_RATING: u32 = 1200u32;
+
+// ratings move faster until the player has played this many games
+pub const PROVISIONAL_GAMES: u32 = 10u32;
+pub const PROVISIONAL_K_FACTOR: u64 = 40u64;
+pub const K_FACTOR: u64 = 20u64;
+
+// defaults for the economic parameters not set at instantiation
+pub const DEFAULT_MIN_STAKE: u128 = 50u128;
+pub const DEFAULT_REWARD_PERCENTAGE: u64 = 1u64;
+pub const DEFAULT_FEE_PERCENTAGE: u64 = 5u64;
+pub const DEFAULT_TURN_DURATION: u64 = 60u64;
+pub const DEFAULT_BETTING_MOVES: u32 = 10u32;
+pub const REVEAL_DURATION: u64 = 300u64;
+
+// shortest turn a game can be configured with
+pub const MIN_TURN_DURATION: u64 = 10u64;
+
+// largest supported board width and height
+pub const MAX_BOARD_SIDE: usize = 20usize;
+// positions tried when checking that a fleet can be placed, bounding the gas of the search
+pub const PLACEMENT_BUDGET: u32 = 10_000u32;
 
-// constants for rewards
-pub const MIN_STAKE: u128 = 50u128;
-pub const REWARD_PERCENTAGE: u128 = 1u128;
-pub const FEE_PERCENTAGE: u128 = 5u128;
-pub const TURN_DURATION: u64 = 60u64;
+// minimum length of the hex encoded salt committed with every board field
+pub const MIN_SALT_LENGTH: usize = 32usize;
@@ -0,0 +1,543 @@
+use cosmwasm_std::{
+    Addr, CosmosMsg, Deps, DepsMut, Env, Event, MessageInfo, Response, StdResult, Storage, Uint128
+};
+
+use crate::contract::execute::{load_opponent, normalize_root, route_fee, transfer, validate_rules};
+use crate::msg::{TournamentMatchResponse, TournamentResponse, TournamentStatusResponse};
+use crate::state::{
+    Board, Game, GameConfig, GameState, Player, ProofFormat, Tournament, TournamentMatch, TournamentStatus, Weapons,
+    CONFIG, GAMES, MAX_TOURNAMENT_PLAYERS, MIN_TURN_DURATION, NEXT_GAME_ID, NEXT_TOURNAMENT_ID, PLAYERS, RATINGS,
+    TOURNAMENTS
+};
+use crate::ContractError;
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_tournament(
+    deps: DepsMut,
+    info: MessageInfo,
+    entry_fee: Uint128,
+    max_players: u32,
+    prizes: Vec<u64>,
+    fleet: Vec<usize>,
+    width: usize,
+    height: usize,
+    turn_duration: Option<u64>
+) -> Result<Response, ContractError> {
+    validate_rules(&fleet, width, height)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    if entry_fee.is_zero() {
+        return Err(ContractError::InvalidStake {});
+    }
+
+    let turn_duration = turn_duration.unwrap_or(config.turn_duration);
+    if turn_duration < MIN_TURN_DURATION {
+        return Err(ContractError::InvalidTurnDuration {});
+    }
+
+    // prizes are percentages of the pool for the first, second, ... finisher
+    if !(2..=MAX_TOURNAMENT_PLAYERS).contains(&max_players)
+        || prizes.is_empty()
+        || prizes.len() > max_players as usize
+        || prizes.iter().sum::<u64>() > 100 {
+        return Err(ContractError::InvalidTournament {});
+    }
+
+    let tournament_id = NEXT_TOURNAMENT_ID.may_load(deps.storage)?.unwrap_or(1);
+    NEXT_TOURNAMENT_ID.save(deps.storage, &(tournament_id + 1))?;
+
+    let tournament = Tournament {
+        creator: info.sender.clone(),
+        token_address: config.token_address,
+        entry_fee,
+        max_players,
+        prizes,
+        fleet,
+        width,
+        height,
+        turn_duration,
+        status: TournamentStatus::Registration,
+        players: vec![],
+        round: 0,
+        matches: vec![],
+        eliminated: vec![],
+    };
+    TOURNAMENTS.save(deps.storage, tournament_id, &tournament)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_tournament")
+        .add_attribute("tournament_id", tournament_id.to_string())
+        .add_attribute("creator", info.sender.to_string())
+        .add_attribute("entry_fee", entry_fee.to_string())
+    )
+}
+
+/// Registers `sender` for the tournament with the entry fee sent through the token contract.
+pub fn register(
+    deps: DepsMut,
+    token_address: &Addr,
+    sender: Addr,
+    tournament_id: u64,
+    amount: Uint128
+) -> Result<Response, ContractError> {
+    let mut tournament = TOURNAMENTS.load(deps.storage, tournament_id)?;
+
+    if *token_address != tournament.token_address {
+        return Err(ContractError::InvalidToken {});
+    }
+
+    if tournament.status != TournamentStatus::Registration {
+        return Err(ContractError::RegistrationClosed {});
+    }
+
+    if tournament.players.contains(&sender) {
+        return Err(ContractError::AlreadyRegistered {});
+    }
+
+    if tournament.players.len() >= tournament.max_players as usize {
+        return Err(ContractError::TournamentFull {});
+    }
+
+    if amount != tournament.entry_fee {
+        return Err(ContractError::InvalidStake {});
+    }
+
+    tournament.players.push(sender.clone());
+    TOURNAMENTS.save(deps.storage, tournament_id, &tournament)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register")
+        .add_attribute("tournament_id", tournament_id.to_string())
+        .add_attribute("player", sender.to_string())
+    )
+}
+
+/// Closes the registration, seeds the bracket by rating and creates the first round games.
+pub fn start_tournament(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    tournament_id: u64
+) -> Result<Response, ContractError> {
+    let mut tournament = TOURNAMENTS.load(deps.storage, tournament_id)?;
+
+    if info.sender != tournament.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if tournament.status != TournamentStatus::Registration {
+        return Err(ContractError::RegistrationClosed {});
+    }
+
+    if tournament.players.len() < 2 {
+        return Err(ContractError::InvalidPlayers {});
+    }
+
+    // higher rated players get the better seeds, registration order breaks ties
+    let mut seeded = vec![];
+    for player in &tournament.players {
+        let rating = RATINGS.may_load(deps.storage, player)?.unwrap_or_default().rating;
+        seeded.push((rating, player.clone()));
+    }
+    seeded.sort_by_key(|(rating, _)| std::cmp::Reverse(*rating));
+
+    let entrants = seed_order(tournament.players.len().next_power_of_two())
+        .into_iter()
+        .map(|seed| seeded.get(seed - 1).map(|(_, player)| player.clone()))
+        .collect();
+
+    tournament.status = TournamentStatus::Running;
+    let (messages, events) = next_round(deps.storage, &env, tournament_id, &mut tournament, entrants)?;
+    TOURNAMENTS.save(deps.storage, tournament_id, &tournament)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_tournament")
+        .add_attribute("tournament_id", tournament_id.to_string())
+        .add_messages(messages)
+        .add_events(events)
+    )
+}
+
+/// Refunds the entry fees of a tournament that has not started.
+pub fn cancel_tournament(
+    deps: DepsMut,
+    info: MessageInfo,
+    tournament_id: u64
+) -> Result<Response, ContractError> {
+    let mut tournament = TOURNAMENTS.load(deps.storage, tournament_id)?;
+
+    if info.sender != tournament.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if tournament.status != TournamentStatus::Registration {
+        return Err(ContractError::RegistrationClosed {});
+    }
+
+    tournament.status = TournamentStatus::Cancelled;
+    TOURNAMENTS.save(deps.storage, tournament_id, &tournament)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "cancel_tournament")
+        .add_attribute("tournament_id", tournament_id.to_string());
+    for player in &tournament.players {
+        response = response.add_message(
+            transfer(player.clone(), tournament.entry_fee, &tournament.asset())?
+        );
+    }
+
+    Ok(response)
+}
+
+/// Commits the board of a tournament player for their match, the game starts
+/// once both players committed.
+pub fn commit_board(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    game_id: u64,
+    board: String
+) -> Result<Response, ContractError> {
+    let board = normalize_root(&board)?;
+    let mut game = GAMES.load(deps.storage, game_id)?;
+
+    if game.config.tournament.is_none() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if game.state.started || game.state.finished {
+        return Err(ContractError::GameStarted {});
+    }
+
+    let mut player = PLAYERS
+        .may_load(deps.storage, (game_id, &info.sender))?
+        .ok_or(ContractError::Unauthorized {})?;
+
+    if !player.board.fields.is_empty() {
+        return Err(ContractError::AlreadyCommitted {});
+    }
+
+    player.board.fields = board;
+    PLAYERS.save(deps.storage, (game_id, &info.sender), &player)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "commit_board")
+        .add_attribute("game_id", game_id.to_string())
+        .add_attribute("player", info.sender.to_string());
+
+    let opponent = load_opponent(deps.as_ref(), game_id, &info.sender)?;
+    if !opponent.board.fields.is_empty() {
+        game.state.started = true;
+        game.state.last_turn_time = env.block.time.seconds();
+        GAMES.save(deps.storage, game_id, &game)?;
+
+        response = response.add_event(
+            Event::new("game_started").add_attribute("game_id", game_id.to_string())
+        );
+    }
+
+    Ok(response)
+}
+
+/// Ends a tournament game neither player committed a board for before the
+/// deadline. Anyone can call it, the match is decided without a winner.
+pub fn expire_match(
+    deps: DepsMut,
+    env: Env,
+    game_id: u64
+) -> Result<Response, ContractError> {
+    let mut game = GAMES.load(deps.storage, game_id)?;
+
+    let (tournament_id, index) = game.config.tournament.ok_or(ContractError::Unauthorized {})?;
+
+    if game.state.started || game.state.finished {
+        return Err(ContractError::GameStarted {});
+    }
+
+    // a player who committed wins the match with TimeoutWin instead
+    let first = PLAYERS.load(deps.storage, (game_id, &game.config.creator))?;
+    let second = load_opponent(deps.as_ref(), game_id, &first.address)?;
+    if !first.board.fields.is_empty() || !second.board.fields.is_empty() {
+        return Err(ContractError::AlreadyCommitted {});
+    }
+
+    if env.block.time.seconds() <= game.state.last_turn_time + game.config.turn_duration {
+        return Err(ContractError::TurnNotExpired {});
+    }
+
+    game.state.finished = true;
+    game.state.settled = true;
+    GAMES.save(deps.storage, game_id, &game)?;
+
+    let (messages, events) = record_result(deps.storage, &env, tournament_id, index, None)?;
+    Ok(Response::new()
+        .add_attribute("action", "expire_match")
+        .add_attribute("game_id", game_id.to_string())
+        .add_messages(messages)
+        .add_events(events)
+    )
+}
+
+/// Records who advances from a settled match, `None` when neither player
+/// placed a legal fleet or committed a board, and moves the tournament on
+/// once the round is over.
+pub fn record_result(
+    storage: &mut dyn Storage,
+    env: &Env,
+    tournament_id: u64,
+    index: usize,
+    winner: Option<Addr>
+) -> Result<(Vec<CosmosMsg>, Vec<Event>), ContractError> {
+    let mut tournament = TOURNAMENTS.load(storage, tournament_id)?;
+
+    let round = tournament.round;
+    let game_match = &mut tournament.matches[index];
+    game_match.decided = true;
+    game_match.winner = winner.clone();
+    for player in [&game_match.players.0, &game_match.players.1].into_iter().flatten() {
+        if Some(player) != winner.as_ref() {
+            tournament.eliminated.push((player.clone(), round));
+        }
+    }
+
+    let mut events = vec![
+        Event::new("match_decided")
+            .add_attribute("tournament_id", tournament_id.to_string())
+            .add_attribute("round", round.to_string())
+            .add_attribute("winner", winner.map_or("none".to_string(), |winner| winner.to_string()))
+    ];
+
+    let mut messages = vec![];
+    if tournament.matches.iter().all(|game_match| game_match.decided) {
+        let entrants = tournament.matches.iter().map(|game_match| game_match.winner.clone()).collect();
+        let (round_messages, round_events) = next_round(storage, env, tournament_id, &mut tournament, entrants)?;
+        messages.extend(round_messages);
+        events.extend(round_events);
+    }
+    TOURNAMENTS.save(storage, tournament_id, &tournament)?;
+
+    Ok((messages, events))
+}
+
+/// Pairs neighbouring entrants of the bracket into the matches of the next round.
+/// Entrants without an opponent get a bye, and rounds decided by byes alone are
+/// skipped. With a single entrant left the tournament is over and the prizes are paid.
+fn next_round(
+    storage: &mut dyn Storage,
+    env: &Env,
+    tournament_id: u64,
+    tournament: &mut Tournament,
+    mut entrants: Vec<Option<Addr>>
+) -> Result<(Vec<CosmosMsg>, Vec<Event>), ContractError> {
+    let mut events = vec![];
+
+    loop {
+        if entrants.len() == 1 {
+            tournament.status = TournamentStatus::Finished;
+            let (messages, prize_events) = pay_prizes(storage, tournament_id, tournament, entrants[0].clone())?;
+            events.extend(prize_events);
+            return Ok((messages, events));
+        }
+
+        tournament.round += 1;
+        tournament.matches = vec![];
+        for (index, pair) in entrants.chunks(2).enumerate() {
+            let players = (pair[0].clone(), pair[1].clone());
+            let game_match = match &players {
+                (Some(first), Some(second)) => {
+                    let game_id = create_match_game(storage, env, tournament_id, tournament, index, first, second)?;
+                    TournamentMatch { players, game_id: Some(game_id), winner: None, decided: false }
+                }
+                (first, second) => {
+                    let winner = first.clone().or(second.clone());
+                    TournamentMatch { players, game_id: None, winner, decided: true }
+                }
+            };
+            tournament.matches.push(game_match);
+        }
+
+        events.push(
+            Event::new("tournament_round")
+                .add_attribute("tournament_id", tournament_id.to_string())
+                .add_attribute("round", tournament.round.to_string())
+        );
+
+        if !tournament.matches.iter().all(|game_match| game_match.decided) {
+            return Ok((vec![], events));
+        }
+        entrants = tournament.matches.iter().map(|game_match| game_match.winner.clone()).collect();
+    }
+}
+
+fn create_match_game(
+    storage: &mut dyn Storage,
+    env: &Env,
+    tournament_id: u64,
+    tournament: &Tournament,
+    index: usize,
+    first: &Addr,
+    second: &Addr
+) -> Result<u64, ContractError> {
+    let game_id = NEXT_GAME_ID.load(storage)?;
+    NEXT_GAME_ID.save(storage, &(game_id + 1))?;
+    let betting_moves = CONFIG.load(storage)?.betting_moves;
+
+    let game = Game {
+        config: GameConfig {
+            asset: tournament.asset(),
+            creator: first.clone(),
+            invitee: Some(second.clone()),
+            stake: Uint128::zero(),
+            fleet: tournament.fleet.clone(),
+            width: tournament.width,
+            height: tournament.height,
+            turn_duration: tournament.turn_duration,
+            fee_percentage: 0,
+            reward_percentage: 0,
+            min_rating: None,
+            max_rating: None,
+            tournament: Some((tournament_id, index)),
+            proof_format: ProofFormat::Hex,
+            salvo: None,
+            extra_turn: false,
+            weapons: Weapons::default(),
+            betting_moves,
+        },
+        state: GameState {
+            started: false,
+            finished: false,
+            turn: first.clone(),
+            // players have one turn duration to commit their boards
+            last_turn_time: env.block.time.seconds(),
+            pending_shot: None,
+            pending_scan: None,
+            draw_offer: None,
+            moves: 0,
+            winner: None,
+            reveal_deadline: 0,
+            settled: false,
+            timed_out: false,
+            paid_to: None,
+        },
+    };
+    GAMES.save(storage, game_id, &game)?;
+
+    for player in [first, second] {
+        PLAYERS.save(storage, (game_id, player), &Player {
+            address: player.clone(),
+            stake: Uint128::zero(),
+            deposited: false,
+            board: Board {
+                fields: String::new(),
+                sank: vec![],
+                revealed: None,
+            },
+            weapons_used: Weapons::default(),
+            scans: vec![],
+        })?;
+    }
+
+    Ok(game_id)
+}
+
+/// Pays the prizes by finishing place. Players knocked out in the same round
+/// share the prizes of the places they occupy, and whatever is not paid out
+/// is retained like a game fee.
+fn pay_prizes(
+    storage: &mut dyn Storage,
+    tournament_id: u64,
+    tournament: &Tournament,
+    champion: Option<Addr>
+) -> Result<(Vec<CosmosMsg>, Vec<Event>), ContractError> {
+    let pool = tournament.entry_fee * Uint128::from(tournament.players.len() as u128);
+    let prize = |place: usize| tournament.prizes
+        .get(place)
+        .map_or(Uint128::zero(), |percentage| pool.multiply_ratio(*percentage, 100u128));
+
+    let mut standings: Vec<Vec<Addr>> = champion.iter().map(|champion| vec![champion.clone()]).collect();
+    let mut eliminated = tournament.eliminated.clone();
+    eliminated.sort_by_key(|(_, round)| std::cmp::Reverse(*round));
+    for round in eliminated.chunk_by(|a, b| a.1 == b.1) {
+        standings.push(round.iter().map(|(player, _)| player.clone()).collect());
+    }
+
+    let mut messages = vec![];
+    let mut events = vec![
+        Event::new("tournament_finished")
+            .add_attribute("tournament_id", tournament_id.to_string())
+            .add_attribute("champion", champion.map_or("none".to_string(), |champion| champion.to_string()))
+    ];
+
+    let mut place = 0;
+    let mut paid = Uint128::zero();
+    for players in standings {
+        let shared: Uint128 = (place..place + players.len()).map(prize).sum();
+        let amount = shared.multiply_ratio(1u128, players.len() as u128);
+        for player in &players {
+            if amount.is_zero() {
+                continue;
+            }
+            paid += amount;
+            messages.push(transfer(player.clone(), amount, &tournament.asset())?);
+            events.push(
+                Event::new("prize")
+                    .add_attribute("tournament_id", tournament_id.to_string())
+                    .add_attribute("place", (place + 1).to_string())
+                    .add_attribute("player", player.to_string())
+                    .add_attribute("amount", amount.to_string())
+            );
+        }
+        place += players.len();
+    }
+
+    let retained = pool.checked_sub(paid).map_err(|_| ContractError::Overflow {})?;
+    if let Some(fee_msg) = route_fee(storage, &tournament.asset(), retained)? {
+        messages.push(fee_msg);
+    }
+
+    Ok((messages, events))
+}
+
+/// Bracket positions of the seeds, so that the best seeds can only meet in the
+/// latest rounds: 1, 4, 2, 3 for four players, 1, 8, 4, 5, 2, 7, 3, 6 for eight.
+fn seed_order(size: usize) -> Vec<usize> {
+    let mut order = vec![1];
+    while order.len() < size {
+        let count = order.len() * 2;
+        order = order.iter().flat_map(|&seed| [seed, count + 1 - seed]).collect();
+    }
+    order
+}
+
+pub fn get_tournament(deps: Deps, tournament_id: u64) -> StdResult<TournamentResponse> {
+    let tournament = TOURNAMENTS.load(deps.storage, tournament_id)?;
+    Ok(TournamentResponse {
+        tournament_id,
+        creator: tournament.creator,
+        token_address: tournament.token_address,
+        entry_fee: tournament.entry_fee,
+        max_players: tournament.max_players,
+        prizes: tournament.prizes,
+        fleet: tournament.fleet,
+        width: tournament.width,
+        height: tournament.height,
+        turn_duration: tournament.turn_duration,
+        status: match tournament.status {
+            TournamentStatus::Registration => TournamentStatusResponse::Registration,
+            TournamentStatus::Running => TournamentStatusResponse::Running,
+            TournamentStatus::Finished => TournamentStatusResponse::Finished,
+            TournamentStatus::Cancelled => TournamentStatusResponse::Cancelled,
+        },
+        players: tournament.players,
+        round: tournament.round,
+        matches: tournament.matches
+            .into_iter()
+            .map(|tournament_match| TournamentMatchResponse {
+                players: tournament_match.players,
+                game_id: tournament_match.game_id,
+                winner: tournament_match.winner,
+                decided: tournament_match.decided,
+            })
+            .collect(),
+    })
+}
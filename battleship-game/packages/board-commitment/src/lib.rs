@@ -0,0 +1,442 @@
+//! Merkle commitment to a battleship board, shared by the game contract and
+//! its clients.
+//!
+//! Every field of the board becomes the leaf `"{row}:{column}:{value}:{salt}"`,
+//! in row-major order, hashed with sha256. Like the Go proof generator, the
+//! leaf level is always padded to an even length and the last node of every
+//! odd level above is paired with itself, so a single field board still has
+//! one proof step.
+//!
+//! Parents are hashed in one of two encodings. [`Encoding::Hex`] hashes the
+//! hex strings of the children concatenated, which is what the Go generator
+//! and the first games did. [`Encoding::Binary`] hashes the raw digests, half
+//! the bytes per level and nodes that travel as 32 bytes instead of 64 chars.
+#![no_std]
+
+extern crate alloc;
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use sha2::{Digest, Sha256};
+
+/// Raw sha256 digest of a tree node.
+pub type Node = [u8; 32];
+
+/// How the children of a node are fed into its hash.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Hex strings of the children, concatenated.
+    #[default]
+    Hex,
+    /// Raw digests of the children, concatenated.
+    Binary,
+}
+
+impl Encoding {
+    /// Number of bytes hashed for every parent node.
+    pub fn parent_len(self) -> usize {
+        match self {
+            Encoding::Hex => 128,
+            Encoding::Binary => 64,
+        }
+    }
+
+    pub fn parent(self, left: &Node, right: &Node) -> Node {
+        match self {
+            Encoding::Hex => {
+                let mut buffer = [0u8; 128];
+                // both halves are exactly twice the digest length
+                hex::encode_to_slice(left, &mut buffer[..64]).unwrap();
+                hex::encode_to_slice(right, &mut buffer[64..]).unwrap();
+                Sha256::digest(buffer).into()
+            }
+            Encoding::Binary => Sha256::new().chain_update(left).chain_update(right).finalize().into(),
+        }
+    }
+}
+
+/// Hex encoded sha256 digest of `data`.
+pub fn hash(data: &str) -> String {
+    hex::encode(Sha256::digest(data.as_bytes()))
+}
+
+/// Preimage of the leaf committing `value` and `salt` at `field`.
+pub fn leaf(field: (usize, usize), value: bool, salt: &str) -> String {
+    format!("{}:{}:{}:{}", field.0, field.1, value, salt)
+}
+
+/// Leaf node committing `value` and `salt` at `field`, the same in both encodings.
+pub fn leaf_node(field: (usize, usize), value: bool, salt: &str) -> Node {
+    Sha256::digest(leaf(field, value, salt).as_bytes()).into()
+}
+
+/// Decodes a hex encoded node, `None` unless it is exactly 32 bytes.
+pub fn decode_node(node: &str) -> Option<Node> {
+    let mut decoded = [0u8; 32];
+    hex::decode_to_slice(node, &mut decoded).ok()?;
+    Some(decoded)
+}
+
+/// Lowercase hex encoding of a node, the form roots and proofs are compared in.
+pub fn encode_node(node: &Node) -> String {
+    hex::encode(node)
+}
+
+/// Number of proof steps for a board of `leaves` fields.
+pub fn tree_depth(leaves: usize) -> usize {
+    let mut nodes = leaves + leaves % 2;
+    let mut depth = 0;
+    while nodes > 1 {
+        nodes = nodes.div_ceil(2);
+        depth += 1;
+    }
+    depth
+}
+
+/// Verifies a hex encoded proof that `value` and `salt` are committed at
+/// `field` of a `width` x `height` board with the given hex encoded root.
+pub fn verify(
+    root: &str,
+    width: usize,
+    height: usize,
+    field: (usize, usize),
+    value: bool,
+    salt: &str,
+    proof: &[String],
+) -> bool {
+    let Some(root) = decode_node(root) else {
+        return false;
+    };
+    let Some(proof) = proof.iter().map(|node| decode_node(node)).collect::<Option<Vec<_>>>() else {
+        return false;
+    };
+    verify_nodes(Encoding::Hex, &root, width, height, field, value, salt, &proof)
+}
+
+/// Verifies that `value` and `salt` are committed at `field` of a
+/// `width` x `height` board with the given root. The position of every sibling
+/// in the path is derived from the field's index, so a proof is only valid for
+/// one field.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_nodes(
+    encoding: Encoding,
+    root: &Node,
+    width: usize,
+    height: usize,
+    field: (usize, usize),
+    value: bool,
+    salt: &str,
+    proof: &[Node],
+) -> bool {
+    let (row, column) = field;
+    if row >= height || column >= width {
+        return false;
+    }
+    if proof.len() != tree_depth(width * height) {
+        return false;
+    }
+
+    climb(encoding, width, field, value, salt, proof).0 == *root
+}
+
+/// Bytes fed to sha256 when verifying `proof` for `field` of a board `width`
+/// fields wide: the leaf preimage and one parent per proof step. Hashing is
+/// where verification spends its gas, so this compares the encodings.
+pub fn hashed_bytes(
+    encoding: Encoding,
+    width: usize,
+    field: (usize, usize),
+    value: bool,
+    salt: &str,
+    proof: &[Node],
+) -> usize {
+    climb(encoding, width, field, value, salt, proof).1
+}
+
+/// Root reached from the leaf of `field` through `proof`, together with the
+/// number of bytes hashed on the way.
+fn climb(
+    encoding: Encoding,
+    width: usize,
+    field: (usize, usize),
+    value: bool,
+    salt: &str,
+    proof: &[Node],
+) -> (Node, usize) {
+    let preimage = leaf(field, value, salt);
+    let mut hashed = preimage.len();
+    let mut current: Node = Sha256::digest(preimage.as_bytes()).into();
+
+    let mut index = field.0 * width + field.1;
+    for sibling in proof {
+        current = if index.is_multiple_of(2) {
+            encoding.parent(&current, sibling)
+        } else {
+            encoding.parent(sibling, &current)
+        };
+        hashed += encoding.parent_len();
+        index /= 2;
+    }
+
+    (current, hashed)
+}
+
+/// A committed board with every level of its tree, able to prove any field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoardCommitment {
+    encoding: Encoding,
+    width: usize,
+    height: usize,
+    // leaves first, every level padded to an even length except the root
+    levels: Vec<Vec<Node>>,
+}
+
+impl BoardCommitment {
+    /// Commits to `fields` with one salt per field in the hex encoding, see
+    /// [`BoardCommitment::with_encoding`].
+    pub fn new(fields: &[Vec<bool>], salts: &[Vec<String>]) -> Option<Self> {
+        Self::with_encoding(fields, salts, Encoding::Hex)
+    }
+
+    /// Commits to `fields` with one salt per field, or `None` if the board is
+    /// empty, not rectangular, or the salts do not have its dimensions.
+    pub fn with_encoding(fields: &[Vec<bool>], salts: &[Vec<String>], encoding: Encoding) -> Option<Self> {
+        let height = fields.len();
+        let width = fields.first().map_or(0, |row| row.len());
+        if width == 0 || salts.len() != height {
+            return None;
+        }
+
+        let mut level = Vec::with_capacity(width * height + 1);
+        for (row, (values, row_salts)) in fields.iter().zip(salts).enumerate() {
+            if values.len() != width || row_salts.len() != width {
+                return None;
+            }
+            for (column, (value, salt)) in values.iter().zip(row_salts).enumerate() {
+                level.push(leaf_node((row, column), *value, salt));
+            }
+        }
+
+        let mut levels = Vec::new();
+        loop {
+            if !level.len().is_multiple_of(2) {
+                level.push(level[level.len() - 1]);
+            }
+            let next: Vec<Node> = level
+                .chunks(2)
+                .map(|pair| encoding.parent(&pair[0], &pair[1]))
+                .collect();
+            levels.push(level);
+            if next.len() == 1 {
+                levels.push(next);
+                break;
+            }
+            level = next;
+        }
+
+        Some(BoardCommitment { encoding, width, height, levels })
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn root_node(&self) -> Node {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    /// Hex encoded root the players commit to when creating or joining a game.
+    pub fn root(&self) -> String {
+        hex::encode(self.root_node())
+    }
+
+    /// Sibling path of `field` from its leaf up to the root, or `None` if the
+    /// field is off the board.
+    pub fn proof_nodes(&self, field: (usize, usize)) -> Option<Vec<Node>> {
+        if field.0 >= self.height || field.1 >= self.width {
+            return None;
+        }
+
+        let mut index = field.0 * self.width + field.1;
+        let mut proof = Vec::with_capacity(self.levels.len() - 1);
+        for level in &self.levels[..self.levels.len() - 1] {
+            proof.push(level[index ^ 1]);
+            index /= 2;
+        }
+        Some(proof)
+    }
+
+    /// Sibling path of `field` with hex encoded nodes.
+    pub fn proof(&self, field: (usize, usize)) -> Option<Vec<String>> {
+        self.proof_nodes(field).map(|proof| proof.iter().map(hex::encode).collect())
+    }
+
+    /// Verifies a proof against this commitment's root, dimensions and encoding.
+    pub fn verify(&self, field: (usize, usize), value: bool, salt: &str, proof: &[Node]) -> bool {
+        verify_nodes(self.encoding, &self.root_node(), self.width, self.height, field, value, salt, proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    // Vectors produced by the Go proof generator's tree for boards salted with
+    // the field index as 64 hex digits.
+    fn salted(fields: Vec<Vec<bool>>) -> (Vec<Vec<bool>>, Vec<Vec<String>>) {
+        let width = fields[0].len();
+        let salts = (0..fields.len())
+            .map(|row| (0..width).map(|column| format!("{:064x}", row * width + column)).collect())
+            .collect();
+        (fields, salts)
+    }
+
+    fn assert_golden(fields: Vec<Vec<bool>>, root: &str, field: (usize, usize), proof: &[&str]) {
+        let (fields, salts) = salted(fields);
+        let board = BoardCommitment::new(&fields, &salts).unwrap();
+        assert_eq!(board.root(), root);
+        assert_eq!(board.encoding(), Encoding::Hex);
+
+        let expected: Vec<String> = proof.iter().map(|node| String::from(*node)).collect();
+        assert_eq!(board.proof(field).unwrap(), expected);
+
+        let value = fields[field.0][field.1];
+        let salt = &salts[field.0][field.1];
+        assert!(verify(root, board.width(), board.height(), field, value, salt, &expected));
+        assert!(!verify(root, board.width(), board.height(), field, !value, salt, &expected));
+    }
+
+    #[test]
+    fn golden_single_field() {
+        assert_golden(
+            vec![vec![true]],
+            "95b1d9ec4ca6ea41edf8c34238ad56dc2d29a0845ba0b11d55be4d6ccd7d96df",
+            (0, 0),
+            &["2db8ecce43a265516165749c2511f2a51a1fda4879d5d24d5e667b6872ed3bb5"],
+        );
+    }
+
+    #[test]
+    fn golden_square_board() {
+        assert_golden(
+            vec![
+                vec![false, false, false],
+                vec![false, true, false],
+                vec![false, false, false],
+            ],
+            "99b59a83a6328a8515ca98987996d7b127ecacdd32ff1be5f2c3f174a3e83ecf",
+            (1, 1),
+            &[
+                "62089eb4edf8b294a027042cbff2e1a71290f5b7fe19bf0e97a3d6037babf9c7",
+                "1d542c85b5d021d91382aafe28931f6d04a24f1138ea9ef38e54913c60ba1452",
+                "e07abaf5bce81e7d2e515204925024efaa03aac0554cb88faadd1b562aabfb64",
+                "d7bfdf6febca2d45970c743abb1c00ce2b88929a2c95a9e39415517d108aeac0",
+            ],
+        );
+    }
+
+    #[test]
+    fn golden_odd_board() {
+        assert_golden(
+            vec![vec![true, true, false], vec![false, false, true]],
+            "10f088f22b4beee00195d7bd1fbda7322e869650f3a60de393bbbf5b2fc25aa7",
+            (1, 2),
+            &[
+                "e241c0a8ebfb9fccad90ae4662a0f96be4167aa7ee1714fecda5d67590b5ff58",
+                "2c8123f448f208b29fea94198be1d9f54f06ffd5a672d2d05a608d09a580eaf3",
+                "a8f1a18111041a33fee4d38cf4c6f1be2a41bd09d1478420b43a7a2f16257fa6",
+            ],
+        );
+    }
+
+    #[test]
+    fn golden_binary_board() {
+        let (fields, salts) = salted(vec![
+            vec![false, false, false],
+            vec![false, true, false],
+            vec![false, false, false],
+        ]);
+        let board = BoardCommitment::with_encoding(&fields, &salts, Encoding::Binary).unwrap();
+        assert_eq!(board.root(), "f8480791955d111ec8c5c5f316daecb97d9e4213e7992073156507e88c6502b2");
+        assert_eq!(
+            board.proof((1, 1)).unwrap(),
+            [
+                "62089eb4edf8b294a027042cbff2e1a71290f5b7fe19bf0e97a3d6037babf9c7",
+                "0f67abd8f77a65da79aa26ac8f58d856686d3ac38c62842bce62a15467506ae8",
+                "2c43060fdabc46b2dc2ae86374d05b1608d5751f0fe20a51c4bd6f0e7dd14adc",
+                "c35e496a7f6fcb4c5f683b798597f6a631b11d535cb4a770f274f203344afd7c",
+            ]
+        );
+
+        let single = BoardCommitment::with_encoding(&[vec![true]], &salted(vec![vec![true]]).1, Encoding::Binary);
+        assert_eq!(
+            single.unwrap().root(),
+            "2680e05669e87b177ac2867570e4f392565202185da5f194ec94cc793bdd1efa"
+        );
+    }
+
+    #[test]
+    fn proofs_are_bound_to_their_field() {
+        for encoding in [Encoding::Hex, Encoding::Binary] {
+            let (fields, salts) = salted(vec![vec![false; 4]; 3]);
+            let board = BoardCommitment::with_encoding(&fields, &salts, encoding).unwrap();
+            for (row, row_salts) in salts.iter().enumerate() {
+                for (column, salt) in row_salts.iter().enumerate() {
+                    let proof = board.proof_nodes((row, column)).unwrap();
+                    assert_eq!(proof.len(), tree_depth(12));
+                    assert!(board.verify((row, column), false, salt, &proof));
+                    // same leaf preimage at another index never verifies
+                    assert!(!board.verify(((row + 1) % 3, column), false, salt, &proof));
+                }
+            }
+            assert_eq!(board.proof((3, 0)), None);
+            assert!(!board.verify((3, 0), false, &salts[0][0], &board.proof_nodes((0, 0)).unwrap()));
+        }
+    }
+
+    #[test]
+    fn encodings_do_not_verify_each_other() {
+        let (fields, salts) = salted(vec![vec![true, false]; 2]);
+        let hex = BoardCommitment::with_encoding(&fields, &salts, Encoding::Hex).unwrap();
+        let binary = BoardCommitment::with_encoding(&fields, &salts, Encoding::Binary).unwrap();
+        assert_ne!(hex.root(), binary.root());
+        assert!(!hex.verify((0, 0), true, &salts[0][0], &binary.proof_nodes((0, 0)).unwrap()));
+        assert!(!binary.verify((0, 0), true, &salts[0][0], &hex.proof_nodes((0, 0)).unwrap()));
+        // malformed hex nodes fail instead of panicking
+        assert!(!verify(&hex.root(), 2, 2, (0, 0), true, &salts[0][0], &[String::from("zz"), String::new()]));
+    }
+
+    #[test]
+    fn binary_parents_hash_half_the_bytes() {
+        let (fields, salts) = salted(vec![vec![false; 10]; 10]);
+        let salt = &salts[9][9];
+        let hashed = |encoding| {
+            let board = BoardCommitment::with_encoding(&fields, &salts, encoding).unwrap();
+            let proof = board.proof_nodes((9, 9)).unwrap();
+            hashed_bytes(encoding, 10, (9, 9), false, salt, &proof)
+        };
+        let preimage = leaf((9, 9), false, salt).len();
+        assert_eq!(hashed(Encoding::Hex), preimage + 7 * 128);
+        assert_eq!(hashed(Encoding::Binary), preimage + 7 * 64);
+    }
+
+    #[test]
+    fn rejects_malformed_boards() {
+        let (fields, salts) = salted(vec![vec![true; 2]; 2]);
+        assert!(BoardCommitment::new(&[], &[]).is_none());
+        assert!(BoardCommitment::new(&fields, &salts[..1]).is_none());
+        assert!(BoardCommitment::new(&[vec![true; 2], vec![true]], &salts).is_none());
+    }
+}
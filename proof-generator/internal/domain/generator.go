@@ -1,6 +1,10 @@
 package domain
 
-import "strconv"
+import (
+	"crypto/rand"
+	"encoding/hex"
+	"fmt"
+)
 
 type Field struct {
 	Row int;
@@ -10,17 +14,21 @@ type Field struct {
 type Generator struct {
 	Board *Board;
 	Index map[Field]int
+	Salts map[Field]string
 	MerkleTree *MerkleTree;
  }
 
 func NewGenerator(board *Board) *Generator {
 	var data []string
 	index := make(map[Field]int)
+	salts := make(map[Field]string)
 
 	for i, row := range board.Fields {
 		for j, value := range row {
-			data = append(data, strconv.FormatBool(value))
-			index[Field{Row: i, Column: j}] = len(data) - 1
+			field := Field{Row: i, Column: j}
+			salts[field] = newSalt()
+			data = append(data, Leaf(field, value, salts[field]))
+			index[field] = len(data) - 1
 		}
 	}
 
@@ -30,18 +38,33 @@ func NewGenerator(board *Board) *Generator {
 		Board: board,
 		MerkleTree: merkleTree,
 		Index: index,
+		Salts: salts,
 	}
 }
 
+// Leaf binds the field value to its coordinate and a secret salt, so a proof
+// opens exactly one cell and the board cannot be brute-forced from the root.
+func Leaf(field Field, value bool, salt string) string {
+	return fmt.Sprintf("%d:%d:%t:%s", field.Row, field.Column, value, salt)
+}
+
+func newSalt() string {
+	salt := make([]byte, 32)
+	if _, err := rand.Read(salt); err != nil {
+		panic(err)
+	}
+	return hex.EncodeToString(salt)
+}
+
 func (Generator *Generator) GetRoot() string {
 	return Generator.MerkleTree.Root.data
 }
 
-func (generator *Generator) GenerateProof(field Field) (string, []ProofStep) {
+func (generator *Generator) GenerateProof(field Field) (string, []string) {
 	return generator.MerkleTree.GenerateProof(generator.Index[field])
 }
 
-func (generator *Generator) VerifyProof(field Field, proof []ProofStep) bool {
-	data := strconv.FormatBool(generator.Board.Fields[field.Row][field.Column])
-	return generator.MerkleTree.VerifyProof(data, proof) 
+func (generator *Generator) VerifyProof(field Field, proof []string) bool {
+	data := Leaf(field, generator.Board.Fields[field.Row][field.Column], generator.Salts[field])
+	return generator.MerkleTree.VerifyProof(generator.Index[field], data, proof)
 }
\ No newline at end of file
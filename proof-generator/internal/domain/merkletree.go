@@ -21,11 +21,6 @@ type MerkleTree struct {
 	Root *Node;
 }
 
-type ProofStep struct {
-	Hash   string `json:"hash"`
-	IsLeft bool   `json:"is_left"`
-}
-
 func NewMerkleTree(data []string) *MerkleTree {
 	merkleTree := &MerkleTree {
 		data: data,
@@ -136,53 +131,45 @@ func appendIfMissing(nodes []*Node, node *Node) []*Node {
 	return append(nodes, node)
 }
 
-func (merkleTree *MerkleTree) GenerateProof(dataIndex int) (string, []ProofStep) {
-	var proof []ProofStep
+// GenerateProof returns the sibling hashes from the leaf up to the root. The
+// side of every sibling follows from the leaf index, so it is not part of the proof.
+func (merkleTree *MerkleTree) GenerateProof(dataIndex int) (string, []string) {
+	var proof []string
 
 	dataToProve := merkleTree.data[dataIndex]
-	leaf := merkleTree.Leaves[dataIndex]
-
-	node := leaf
-	for node != nil {
-		
-		if node.left != nil {
-			proof = append(
-				proof, 
-				ProofStep {
-					Hash: node.left.data,
-					IsLeft: true,
-				},
-			)
-		}
+	level := slices.Clone(merkleTree.Leaves)
+	index := dataIndex
 
-		if node.right != nil {
-			proof = append(
-				proof, 
-				ProofStep {
-					Hash: node.right.data,
-					IsLeft: false,
-				},
-			)
+	for len(level) > 1 {
+		if len(level) % 2 != 0 {
+			level = append(level, level[len(level)-1])
 		}
 
-		node = node.parent
+		proof = append(proof, level[index^1].data)
+
+		var nextLevel []*Node
+		for i := 0; i < len(level); i += 2 {
+			nextLevel = append(nextLevel, level[i].parent)
+		}
+		level = nextLevel
+		index /= 2
 	}
 
 	return dataToProve, proof
 }
 
-func (merkleTree *MerkleTree) VerifyProof(data string, proof []ProofStep) bool {
+func (merkleTree *MerkleTree) VerifyProof(dataIndex int, data string, proof []string) bool {
 	currentHash := hash(data)
+	index := dataIndex
 
-	for _, step := range proof {
-		if step.IsLeft {
-			currentHash = hash(step.Hash + currentHash)
-			continue
+	for _, sibling := range proof {
+		if index % 2 == 0 {
+			currentHash = hash(currentHash + sibling)
+		} else {
+			currentHash = hash(sibling + currentHash)
 		}
-		currentHash = hash(currentHash + step.Hash)
+		index /= 2
 	}
 
 	return currentHash == merkleTree.Root.data
 }
-
-
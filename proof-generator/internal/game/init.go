@@ -1,6 +1,7 @@
 package game
 
 import (
+	"encoding/base64"
 	"encoding/json"
 	"fmt"
 	"os"
@@ -18,20 +19,18 @@ type PlayerFile struct {
 type InstantiateFile struct {
 	Admin        string `json:"admin"`
 	TokenAddress string `json:"token_address"`
-	Ships        int    `json:"ships"`
+	Fleet        []int  `json:"fleet"`
 }
 
-type PlayerInstantiate struct {
-	Address string `json:"address"`
-	Stake   string `json:"stake"`
-	Board   string `json:"board"`
+type PlayerSetup struct {
+	Address string
+	Stake   string
+	Board   string
 }
 
 type InstantiateMsg struct {
-	Admin        string              `json:"admin"`
-	TokenAddress string              `json:"token_address"`
-	Ships        int                 `json:"ships"`
-	Players      []PlayerInstantiate `json:"players"`
+	Admin        string `json:"admin"`
+	TokenAddress string `json:"token_address"`
 }
 
 type CodeIDs struct {
@@ -53,20 +52,20 @@ func LoadCodeIDs() (*CodeIDs, error) {
 	return &ids, nil
 }
 
-func LoadPlayerData(path string, generator *domain.Generator) (PlayerInstantiate, error) {
+func LoadPlayerData(path string, generator *domain.Generator) (PlayerSetup, error) {
 	file, err := os.ReadFile(path)
 	if err != nil {
-		return PlayerInstantiate{}, fmt.Errorf("failed to read file %s: %w", path, err)
+		return PlayerSetup{}, fmt.Errorf("failed to read file %s: %w", path, err)
 	}
 
 	var data PlayerFile
 	if err := json.Unmarshal(file, &data); err != nil {
-		return PlayerInstantiate{}, fmt.Errorf("invalid player file %s: %w", path, err)
+		return PlayerSetup{}, fmt.Errorf("invalid player file %s: %w", path, err)
 	}
 	*generator = *domain.NewGenerator(domain.NewBoard(data.Board))
 	root := generator.GetRoot()
 
-	return PlayerInstantiate{
+	return PlayerSetup{
 		Address: data.Address,
 		Stake:   data.Stake,
 		Board:   root,
@@ -100,14 +99,6 @@ func InstantiateContract(msg InstantiateMsg, codeId string) error {
 
 	fmt.Println("Contract instantiated!")
 
-	address, err := GetLastContractAddressByCode(codeId)
-	if err != nil {
-		fmt.Println("Could not fetch contract address automatically:", err)
-	} else {
-		_ = saveContractAddress(address)
-		fmt.Println("Contract address:", address)
-	}
-
 	return nil
 }
 
@@ -133,16 +124,13 @@ func GetLastContractAddressByCode(codeID string) (string, error) {
 	return result.Contracts[len(result.Contracts)-1], nil
 }
 
-func saveContractAddress(addr string) error {
-	type ContractInfo struct {
-		Address string `json:"address"`
-	}
+func saveContractInfo(info ContractInfo) error {
 	file, err := os.Create("contract.json")
 	if err != nil {
 		return err
 	}
 	defer file.Close()
-	return json.NewEncoder(file).Encode(ContractInfo{Address: addr})
+	return json.NewEncoder(file).Encode(info)
 }
 
 func SetCW20Minter(tokenAddr, minter, from string) error {
@@ -173,17 +161,20 @@ func SetCW20Minter(tokenAddr, minter, from string) error {
 	return nil
 }
 
-func ApproveCW20(contractAddr, tokenAddr, player string, amount string) error {
+func CreateGame(contractAddr, from string, stake string, fleet []int, width, height int, board string) error {
 	msg := map[string]interface{}{
-		"increase_allowance": map[string]interface{}{
-			"spender": contractAddr,
-			"amount":  amount,
+		"create_game": map[string]interface{}{
+			"stake":  stake,
+			"fleet":  fleet,
+			"width":  width,
+			"height": height,
+			"board":  board,
 		},
 	}
 	msgBytes, _ := json.Marshal(msg)
 
-	cmd := exec.Command("wasmd", "tx", "wasm", "execute", tokenAddr, string(msgBytes),
-		"--from="+player,
+	cmd := exec.Command("wasmd", "tx", "wasm", "execute", contractAddr, string(msgBytes),
+		"--from="+from,
 		"--chain-id=localnet",
 		"--keyring-backend=test",
 		"--gas=auto", "--gas-adjustment=1.3",
@@ -191,24 +182,34 @@ func ApproveCW20(contractAddr, tokenAddr, player string, amount string) error {
 		"-y",
 	)
 
-	fmt.Printf("Approving CW20 allowance for %s...\n", player)
+	fmt.Printf("Creating game from %s...\n", from)
 
 	output, err := cmd.CombinedOutput()
 	if err != nil {
-		return fmt.Errorf("approval failed for %s: %v\nOutput: %s", player, err, string(output))
+		return fmt.Errorf("create_game failed from %s: %v\nOutput: %s", from, err, string(output))
 	}
 
-	fmt.Println("Approved:", player)
+	fmt.Println("Game created by:", from)
 	return nil
 }
 
-func StartGame(contractAddr string, from string) error {
+// SendCW20 sends tokens to the game contract together with a receive hook,
+// which is how stakes are deposited and games are joined.
+func SendCW20(tokenAddr, contractAddr, from, amount string, hook interface{}) error {
+	hookBytes, err := json.Marshal(hook)
+	if err != nil {
+		return fmt.Errorf("failed to marshal receive hook: %w", err)
+	}
 	msg := map[string]interface{}{
-		"start_game": map[string]interface{}{},
+		"send": map[string]interface{}{
+			"contract": contractAddr,
+			"amount":   amount,
+			"msg":      base64.StdEncoding.EncodeToString(hookBytes),
+		},
 	}
 	msgBytes, _ := json.Marshal(msg)
 
-	cmd := exec.Command("wasmd", "tx", "wasm", "execute", contractAddr, string(msgBytes),
+	cmd := exec.Command("wasmd", "tx", "wasm", "execute", tokenAddr, string(msgBytes),
 		"--from="+from,
 		"--chain-id=localnet",
 		"--keyring-backend=test",
@@ -217,19 +218,54 @@ func StartGame(contractAddr string, from string) error {
 		"-y",
 	)
 
-	fmt.Printf("Starting game from %s...\n", from)
+	fmt.Printf("Sending %s tokens from %s...\n", amount, from)
 
 	output, err := cmd.CombinedOutput()
 	if err != nil {
-		return fmt.Errorf("start_game failed from %s: %v\nOutput: %s", from, err, string(output))
+		return fmt.Errorf("send failed from %s: %v\nOutput: %s", from, err, string(output))
 	}
 
-	time.Sleep(5 * time.Second)
-
-	fmt.Println("Game started by:", from)
+	fmt.Println("Tokens sent by:", from)
 	return nil
 }
 
+// LastOpenGameID returns the id of the newest game of creator still waiting
+// for an opponent.
+func LastOpenGameID(contractAddr, creator string) (uint64, error) {
+	query := map[string]interface{}{
+		"get_open_games": map[string]interface{}{},
+	}
+	queryBytes, err := json.Marshal(query)
+	if err != nil {
+		return 0, fmt.Errorf("failed to marshal get_open_games query: %w", err)
+	}
+
+	cmd := exec.Command("wasmd", "query", "wasm", "contract-state", "smart", contractAddr, string(queryBytes), "--output=json")
+	output, err := cmd.CombinedOutput()
+	if err != nil {
+		return 0, fmt.Errorf("failed to query open games: %v\nOutput: %s", err, output)
+	}
+
+	var parsed struct {
+		Data struct {
+			Games []struct {
+				GameID  uint64 `json:"game_id"`
+				Creator string `json:"creator"`
+			} `json:"games"`
+		} `json:"data"`
+	}
+	if err := json.Unmarshal(output, &parsed); err != nil {
+		return 0, fmt.Errorf("failed to parse open games: %w\nOutput: %s", err, output)
+	}
+
+	for i := len(parsed.Data.Games) - 1; i >= 0; i-- {
+		if parsed.Data.Games[i].Creator == creator {
+			return parsed.Data.Games[i].GameID, nil
+		}
+	}
+	return 0, fmt.Errorf("no open game created by %s", creator)
+}
+
 func QueryBalance(contractAddr, address string) {
 	cmd := exec.Command("wasmd", "query", "wasm", "contract-state", "smart", contractAddr,
 		fmt.Sprintf(`{"balance":{"address":"%s"}}`, address),
@@ -258,29 +294,23 @@ func InitGame(player1Generator *domain.Generator, player2Generator *domain.Gener
 		return
 	}
 
-	var players []PlayerInstantiate
 	player1, err := LoadPlayerData("player1.json", player1Generator)
 	if err != nil {
 		fmt.Println(err)
 		return
 	}
-	players = append(players, player1)
 
 	player2, err := LoadPlayerData("player2.json", player2Generator)
 	if err != nil {
 		fmt.Println(err)
 		return
 	}
-	players = append(players, player2)
 
 	msg := InstantiateMsg{
 		Admin:        meta.Admin,
 		TokenAddress: meta.TokenAddress,
-		Ships:        meta.Ships,
-		Players:      players,
 	}
 
-
 	ids, err := LoadCodeIDs()
 	if err != nil {
 		fmt.Println("Failed to load code IDs:", err)
@@ -293,13 +323,14 @@ func InitGame(player1Generator *domain.Generator, player2Generator *domain.Gener
 		return
 	}
 
+	time.Sleep(5 * time.Second)
+
 	contractAddr, err := GetLastContractAddressByCode(ids.GameCodeID)
 	if err != nil {
 		fmt.Println("Could not fetch contract address automatically:", err)
 		return
 	}
-
-	time.Sleep(5 * time.Second)
+	fmt.Println("Contract address:", contractAddr)
 
 	err = SetCW20Minter(msg.TokenAddress, contractAddr, msg.Admin)
 	if err != nil {
@@ -309,57 +340,75 @@ func InitGame(player1Generator *domain.Generator, player2Generator *domain.Gener
 
 	time.Sleep(5 * time.Second)
 
-	err = ApproveCW20(contractAddr, msg.TokenAddress, "player1", player1.Stake)
+	width := len(player1Generator.Board.Fields[0])
+	height := len(player1Generator.Board.Fields)
+	err = CreateGame(contractAddr, "player1", player1.Stake, meta.Fleet, width, height, player1.Board)
 	if err != nil {
-		fmt.Println("Approval failed for", player1.Address, ":", err)
+		fmt.Println("Could not create game:", err)
 		return
 	}
 
 	time.Sleep(5 * time.Second)
 
-	err = ApproveCW20(contractAddr, msg.TokenAddress, "player2", player2.Stake)
+	gameID, err := LastOpenGameID(contractAddr, player1.Address)
+	if err != nil {
+		fmt.Println("Could not find the created game:", err)
+		return
+	}
+	_ = saveContractInfo(ContractInfo{Address: contractAddr, GameID: gameID})
+	fmt.Println("Game id:", gameID)
+
+	deposit := map[string]interface{}{
+		"deposit": map[string]interface{}{"game_id": gameID},
+	}
+	err = SendCW20(msg.TokenAddress, contractAddr, "player1", player1.Stake, deposit)
 	if err != nil {
-		fmt.Println("Approval failed for", player2.Address, ":", err)
+		fmt.Println("Deposit failed for", player1.Address, ":", err)
 		return
 	}
 
 	time.Sleep(5 * time.Second)
 
-	err = StartGame(contractAddr, msg.Players[0].Address)
+	join := map[string]interface{}{
+		"join_game": map[string]interface{}{"game_id": gameID, "board": player2.Board},
+	}
+	err = SendCW20(msg.TokenAddress, contractAddr, "player2", player2.Stake, join)
 	if err != nil {
-		fmt.Println("Could not start game from", msg.Players[0].Address, ":", err)
+		fmt.Println("Join failed for", player2.Address, ":", err)
 		return
 	}
 
-	fmt.Println("Game successfully instantiated!")
+	time.Sleep(5 * time.Second)
+
+	fmt.Println("Game successfully started!")
 }
 
-func CheckGameStarted(contractAddr string) (bool, error) {
+func CheckGameStarted(contractAddr string, gameID uint64) (bool, error) {
 	query := map[string]interface{}{
-		"get_started": map[string]interface{}{},
+		"get_game_state": map[string]interface{}{"game_id": gameID},
 	}
 	queryBytes, err := json.Marshal(query)
 	if err != nil {
-		return false, fmt.Errorf("failed to marshal get_started query: %w", err)
+		return false, fmt.Errorf("failed to marshal get_game_state query: %w", err)
 	}
 
 	cmd := exec.Command("wasmd", "query", "wasm", "contract-state", "smart", contractAddr, string(queryBytes), "--output=json")
 	output, err := cmd.CombinedOutput()
 	if err != nil {
-		return false, fmt.Errorf("failed to query get_started: %v\nOutput: %s", err, output)
+		return false, fmt.Errorf("failed to query get_game_state: %v\nOutput: %s", err, output)
 	}
 	println(string(output))
 
 	var parsed struct {
 		Data struct {
-			Value bool `json:"value"`
+			Started bool `json:"started"`
 		} `json:"data"`
 	}
 	if err := json.Unmarshal(output, &parsed); err != nil {
-		return false, fmt.Errorf("failed to parse get_started response: %w\nOutput: %s", err, output)
+		return false, fmt.Errorf("failed to parse get_game_state response: %w\nOutput: %s", err, output)
 	}
 
-	return parsed.Data.Value, nil
+	return parsed.Data.Started, nil
 }
 
 func QueryCW20Minter(cw20Addr string) (string, error) {
@@ -389,25 +438,3 @@ func QueryCW20Minter(cw20Addr string) (string, error) {
 	return parsed.Data.Minter, nil
 }
 
-
-func CheckAllowance(cw20Addr, ownerAddr, spenderAddr string) error {
-	query := map[string]interface{}{
-		"allowance": map[string]interface{}{
-			"owner":   ownerAddr,
-			"spender": spenderAddr,
-		},
-	}
-	queryBytes, err := json.Marshal(query)
-	if err != nil {
-		return fmt.Errorf("failed to marshal allowance query: %w", err)
-	}
-
-	cmd := exec.Command("wasmd", "query", "wasm", "contract-state", "smart", cw20Addr, string(queryBytes), "--output=json")
-	output, err := cmd.CombinedOutput()
-	if err != nil {
-		return fmt.Errorf("failed to query allowance: %v\nOutput: %s", err, output)
-	}
-
-	fmt.Printf("Allowance for spender %s from owner %s:\n%s\n", spenderAddr, ownerAddr, string(output))
-	return nil
-}
\ No newline at end of file
@@ -13,12 +13,21 @@ type PlayerFileMeta struct {
 	Address string `json:"address"`
 }
 
-func PlayMove(player string, contractAddr string, x, y int, value bool, proof []domain. ProofStep) error {
+// ContractInfo is the game contract and the game played in it, stored in
+// contract.json once the game is created.
+type ContractInfo struct {
+	Address string `json:"address"`
+	GameID  uint64 `json:"game_id"`
+}
+
+func PlayMove(player string, contractAddr string, gameID uint64, x, y int, value bool, salt string, proof []string) error {
 	msg := map[string]interface{}{
 		"play": map[string]interface{}{
-			"field": [2]int{x, y},
-			"value": value,
-			"proof": proof,
+			"game_id": gameID,
+			"field":   [2]int{x, y},
+			"value":   value,
+			"salt":    salt,
+			"proof":   proof,
 		},
 	}
 
@@ -49,9 +58,9 @@ func PlayMove(player string, contractAddr string, x, y int, value bool, proof []
 
 
 func Play(playerName string, generator *domain.Generator) {
-	contractAddr, err := LoadContractAddress()
+	contract, err := LoadContractInfo()
 	if err != nil {
-		fmt.Println("Failed to load contract address:", err)
+		fmt.Println("Failed to load contract info:", err)
 		return
 	}
 
@@ -70,10 +79,11 @@ func Play(playerName string, generator *domain.Generator) {
 
 	field := domain.Field{Row: x, Column: y}
 	value := generator.Board.Fields[x][y]
+	salt := generator.Salts[field]
 	_, proof := generator.GenerateProof(field)
 
 	time.Sleep(3 * time.Second)
-	err = PlayMove(playerData.Address, contractAddr, x, y, value, proof)
+	err = PlayMove(playerData.Address, contract.Address, contract.GameID, x, y, value, salt, proof)
 	if err != nil {
 		fmt.Println("Move failed:", err)
 		return
@@ -94,16 +104,14 @@ func LoadPlayerMeta(path string) (PlayerFileMeta, error) {
 	return data, nil
 }
 
-func LoadContractAddress() (string, error) {
+func LoadContractInfo() (ContractInfo, error) {
 	file, err := os.ReadFile("contract.json")
 	if err != nil {
-		return "", err
-	}
-	var data struct {
-		Address string `json:"address"`
+		return ContractInfo{}, err
 	}
+	var data ContractInfo
 	if err := json.Unmarshal(file, &data); err != nil {
-		return "", err
+		return ContractInfo{}, err
 	}
-	return data.Address, nil
+	return data, nil
 }
\ No newline at end of file